@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS oauth_audit_events (
+    id              BIGSERIAL   PRIMARY KEY,
+    discord_user_id TEXT        NOT NULL,
+    anilist_id      BIGINT,
+    event           TEXT        NOT NULL,
+    actor           TEXT        NOT NULL,
+    reason          TEXT,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_oauth_audit_events_discord_user_id
+    ON oauth_audit_events (discord_user_id, created_at DESC);
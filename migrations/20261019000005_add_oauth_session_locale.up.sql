@@ -0,0 +1,2 @@
+ALTER TABLE oauth_sessions
+ADD COLUMN IF NOT EXISTS locale TEXT;
@@ -0,0 +1,2 @@
+ALTER TABLE oauth_sessions
+DROP COLUMN IF EXISTS browser_binding_hash;
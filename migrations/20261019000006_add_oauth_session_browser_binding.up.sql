@@ -0,0 +1,2 @@
+ALTER TABLE oauth_sessions
+ADD COLUMN IF NOT EXISTS browser_binding_hash TEXT;
@@ -0,0 +1,2 @@
+ALTER TABLE oauth_audit_events
+DROP COLUMN IF EXISTS anonymized_at;
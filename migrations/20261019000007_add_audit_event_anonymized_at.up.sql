@@ -0,0 +1,2 @@
+ALTER TABLE oauth_audit_events
+ADD COLUMN IF NOT EXISTS anonymized_at TIMESTAMPTZ;
@@ -0,0 +1,2 @@
+ALTER TABLE oauth_credentials
+DROP COLUMN IF EXISTS last_probed_at;
@@ -0,0 +1,2 @@
+ALTER TABLE oauth_credentials
+ADD COLUMN IF NOT EXISTS last_probed_at TIMESTAMPTZ;
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS oauth_context_nonces (
+    nonce      TEXT        PRIMARY KEY,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_oauth_context_nonces_expires_at
+    ON oauth_context_nonces (expires_at);
@@ -0,0 +1,14 @@
+-- Timestamps are stored as Unix seconds.
+CREATE TABLE IF NOT EXISTS oauth_credentials (
+    discord_user_id    TEXT    PRIMARY KEY,
+    anilist_id         INTEGER NOT NULL,
+    access_token       TEXT    NOT NULL,
+    refresh_token      TEXT,
+    token_expires_at   INTEGER,
+    token_updated_at   INTEGER NOT NULL DEFAULT (unixepoch()),
+    relink_required_at INTEGER,
+    relink_reason      TEXT,
+    last_probed_at     INTEGER,
+    created_at         INTEGER NOT NULL DEFAULT (unixepoch()),
+    CONSTRAINT uq_oauth_credentials_anilist_id UNIQUE (anilist_id)
+);
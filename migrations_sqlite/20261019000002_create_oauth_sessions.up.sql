@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS oauth_sessions (
+    state                TEXT    PRIMARY KEY,
+    discord_user_id      TEXT    NOT NULL,
+    expires_at           INTEGER NOT NULL,
+    used_at              INTEGER,
+    created_at           INTEGER NOT NULL DEFAULT (unixepoch()),
+    locale               TEXT,
+    browser_binding_hash TEXT
+);
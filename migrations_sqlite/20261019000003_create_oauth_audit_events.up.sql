@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS oauth_audit_events (
+    id              INTEGER PRIMARY KEY AUTOINCREMENT,
+    discord_user_id TEXT    NOT NULL,
+    anilist_id      INTEGER,
+    event           TEXT    NOT NULL,
+    actor           TEXT    NOT NULL,
+    reason          TEXT,
+    created_at      INTEGER NOT NULL DEFAULT (unixepoch())
+);
+
+CREATE INDEX IF NOT EXISTS idx_oauth_audit_events_discord_user_id
+    ON oauth_audit_events (discord_user_id, created_at DESC);
@@ -0,0 +1,424 @@
+use annie_mei_auth::utils::{
+    config::AppConfig,
+    functions::{
+        fetch_credential_by_anilist_id, fetch_credential_by_discord_user, fetch_migration_status,
+        force_relink_oauth_credential, list_relink_required_credentials, purge_oauth_sessions,
+        unlink_oauth_credential,
+    },
+    retention::enforce_retention,
+    structs::{CredentialSummary, MigrationStatus},
+};
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::{fmt, path::PathBuf};
+
+const AUDIT_ACTOR_CLI: &str = "cli";
+
+/// Operational commands for Annie Mei AniList links.
+#[derive(Debug, Parser)]
+#[command(name = "annie-mei-auth-admin", version, about)]
+struct Cli {
+    /// How results are printed.
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table, global = true)]
+    format: OutputFormat,
+
+    /// TOML config file; environment variables override its values.
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Table,
+    Json,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Look up a linked account by Discord user ID or AniList ID.
+    Lookup(LinkTarget),
+    /// Force a user to relink their AniList account.
+    Relink {
+        #[arg(long)]
+        discord_user_id: String,
+        /// Stored as `relink_reason` on the credential.
+        #[arg(long)]
+        reason: String,
+    },
+    /// Remove a user's AniList link.
+    Unlink {
+        #[arg(long)]
+        discord_user_id: String,
+    },
+    /// List users whose AniList link must be reconnected.
+    RelinkRequired {
+        #[arg(long, default_value_t = 100)]
+        limit: i64,
+    },
+    /// Delete used and expired OAuth sessions.
+    PurgeSessions {
+        /// Also delete sessions that could still complete a callback.
+        #[arg(long)]
+        all: bool,
+    },
+    /// Apply the configured retention policies once.
+    EnforceRetention,
+    /// Show applied and pending database migrations.
+    Migrations,
+}
+
+#[derive(Debug, Args)]
+#[group(required = true, multiple = false)]
+struct LinkTarget {
+    #[arg(long)]
+    discord_user_id: Option<String>,
+    #[arg(long)]
+    anilist_id: Option<i64>,
+}
+
+/// Plain-text table with left-aligned, padded columns.
+struct Table {
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn key_value(pairs: Vec<(&'static str, String)>) -> Self {
+        Self {
+            headers: vec!["field", "value"],
+            rows: pairs
+                .into_iter()
+                .map(|(key, value)| vec![key.to_string(), value])
+                .collect(),
+        }
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut widths: Vec<usize> = self.headers.iter().map(|header| header.len()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let separators: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+
+        writeln!(f, "{}", format_row(&self.headers, &widths))?;
+        writeln!(f, "{}", format_row(&separators, &widths))?;
+        for row in &self.rows {
+            writeln!(f, "{}", format_row(row, &widths))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn format_row<S: AsRef<str>>(cells: &[S], widths: &[usize]) -> String {
+    cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| format!("{:<width$}", cell.as_ref()))
+        .collect::<Vec<_>>()
+        .join("  ")
+        .trim_end()
+        .to_string()
+}
+
+fn emit<T: Serialize>(
+    format: OutputFormat,
+    value: &T,
+    table: impl FnOnce() -> Table,
+) -> Result<()> {
+    match format {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(value).context("Failed to serialize output")?
+        ),
+        OutputFormat::Table => print!("{}", table()),
+    }
+
+    Ok(())
+}
+
+fn display_time(value: Option<DateTime<Utc>>) -> String {
+    value.map_or_else(|| "-".to_string(), |time| time.to_rfc3339())
+}
+
+fn credential_table(credential: &CredentialSummary) -> Table {
+    Table::key_value(vec![
+        ("discord_user_id", credential.discord_user_id.clone()),
+        ("anilist_id", credential.anilist_id.to_string()),
+        ("access_token", credential.access_token.clone()),
+        (
+            "refresh_token",
+            credential
+                .refresh_token
+                .clone()
+                .unwrap_or_else(|| "-".to_string()),
+        ),
+        (
+            "token_expires_at",
+            display_time(credential.token_expires_at),
+        ),
+        ("token_updated_at", credential.token_updated_at.to_rfc3339()),
+        (
+            "relink_required_at",
+            display_time(credential.relink_required_at),
+        ),
+        (
+            "relink_reason",
+            credential
+                .relink_reason
+                .clone()
+                .unwrap_or_else(|| "-".to_string()),
+        ),
+        ("created_at", credential.created_at.to_rfc3339()),
+    ])
+}
+
+fn relink_required_table(credentials: &[CredentialSummary]) -> Table {
+    Table {
+        headers: vec![
+            "discord_user_id",
+            "anilist_id",
+            "relink_required_at",
+            "relink_reason",
+        ],
+        rows: credentials
+            .iter()
+            .map(|credential| {
+                vec![
+                    credential.discord_user_id.clone(),
+                    credential.anilist_id.to_string(),
+                    display_time(credential.relink_required_at),
+                    credential
+                        .relink_reason
+                        .clone()
+                        .unwrap_or_else(|| "-".to_string()),
+                ]
+            })
+            .collect(),
+    }
+}
+
+fn migrations_table(migrations: &[MigrationStatus]) -> Table {
+    Table {
+        headers: vec!["version", "description", "state", "installed_on"],
+        rows: migrations
+            .iter()
+            .map(|migration| {
+                vec![
+                    migration.version.to_string(),
+                    migration.description.clone(),
+                    migration.state.as_str().to_string(),
+                    display_time(migration.installed_on),
+                ]
+            })
+            .collect(),
+    }
+}
+
+async fn run(cli: Cli, config: AppConfig) -> Result<()> {
+    let database_url = config
+        .database_url
+        .as_deref()
+        .context("DATABASE_URL was not found")?;
+    let pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect(database_url)
+        .await
+        .context("Failed to connect to the database")?;
+    let salt = config.user_id_hash_salt.as_str();
+
+    match cli.command {
+        Command::Lookup(target) => {
+            let credential = match (target.discord_user_id, target.anilist_id) {
+                (Some(discord_user_id), _) => {
+                    fetch_credential_by_discord_user(&discord_user_id, salt, &pool).await?
+                }
+                (None, Some(anilist_id)) => {
+                    fetch_credential_by_anilist_id(anilist_id, salt, &pool).await?
+                }
+                (None, None) => unreachable!("clap requires one lookup target"),
+            };
+            let summary = credential.as_ref().map(CredentialSummary::from);
+
+            emit(cli.format, &summary, || match &summary {
+                Some(summary) => credential_table(summary),
+                None => Table::key_value(vec![("result", "no linked account found".to_string())]),
+            })?;
+        }
+        Command::Relink {
+            discord_user_id,
+            reason,
+        } => {
+            let reason = reason.trim();
+            if reason.is_empty() {
+                bail!("--reason must not be empty");
+            }
+
+            let updated = force_relink_oauth_credential(
+                &discord_user_id,
+                reason,
+                AUDIT_ACTOR_CLI,
+                salt,
+                &pool,
+            )
+            .await?
+            .is_some();
+
+            emit(
+                cli.format,
+                &json!({ "discord_user_id": discord_user_id, "relink_required": updated, "reason": reason }),
+                || {
+                    Table::key_value(vec![
+                        ("discord_user_id", discord_user_id.clone()),
+                        ("relink_required", updated.to_string()),
+                        ("reason", reason.to_string()),
+                    ])
+                },
+            )?;
+        }
+        Command::Unlink { discord_user_id } => {
+            let deleted = unlink_oauth_credential(&discord_user_id, AUDIT_ACTOR_CLI, salt, &pool)
+                .await?
+                .is_some();
+
+            emit(
+                cli.format,
+                &json!({ "discord_user_id": discord_user_id, "unlinked": deleted }),
+                || {
+                    Table::key_value(vec![
+                        ("discord_user_id", discord_user_id.clone()),
+                        ("unlinked", deleted.to_string()),
+                    ])
+                },
+            )?;
+        }
+        Command::RelinkRequired { limit } => {
+            if limit <= 0 {
+                bail!("--limit must be a positive integer");
+            }
+
+            let credentials: Vec<CredentialSummary> =
+                list_relink_required_credentials(limit, &pool)
+                    .await?
+                    .iter()
+                    .map(CredentialSummary::from)
+                    .collect();
+
+            emit(cli.format, &credentials, || {
+                relink_required_table(&credentials)
+            })?;
+        }
+        Command::PurgeSessions { all } => {
+            let deleted = purge_oauth_sessions(all, &pool).await?;
+
+            emit(
+                cli.format,
+                &json!({ "deleted_sessions": deleted, "included_pending": all }),
+                || {
+                    Table::key_value(vec![
+                        ("deleted_sessions", deleted.to_string()),
+                        ("included_pending", all.to_string()),
+                    ])
+                },
+            )?;
+        }
+        Command::EnforceRetention => {
+            let report = enforce_retention(&config.retention, salt, &pool).await?;
+
+            emit(cli.format, &report, || {
+                Table::key_value(vec![
+                    ("sessions_deleted", report.sessions_deleted.to_string()),
+                    (
+                        "credentials_deleted",
+                        report.credentials_deleted.to_string(),
+                    ),
+                    (
+                        "audit_events_anonymized",
+                        report.audit_events_anonymized.to_string(),
+                    ),
+                ])
+            })?;
+        }
+        Command::Migrations => {
+            let migrations = fetch_migration_status(&pool).await?;
+
+            emit(cli.format, &migrations, || migrations_table(&migrations))?;
+        }
+    }
+
+    pool.close().await;
+    Ok(())
+}
+
+#[rocket::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    let config = AppConfig::load(cli.config.as_deref())?;
+
+    run(cli, config).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cli, Command, OutputFormat, Table};
+    use clap::{CommandFactory, Parser};
+
+    #[test]
+    fn cli_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn lookup_requires_exactly_one_target() {
+        assert!(Cli::try_parse_from(["admin", "lookup"]).is_err());
+        assert!(
+            Cli::try_parse_from([
+                "admin",
+                "lookup",
+                "--discord-user-id",
+                "123",
+                "--anilist-id",
+                "42"
+            ])
+            .is_err()
+        );
+
+        let cli =
+            Cli::try_parse_from(["admin", "lookup", "--anilist-id", "42", "--format", "json"])
+                .expect("single lookup target should parse");
+        assert_eq!(cli.format, OutputFormat::Json);
+        assert!(matches!(
+            cli.command,
+            Command::Lookup(ref target) if target.anilist_id == Some(42)
+        ));
+    }
+
+    #[test]
+    fn table_pads_columns_to_widest_cell() {
+        let table = Table {
+            headers: vec!["id", "reason"],
+            rows: vec![
+                vec!["1".to_string(), "token_expired".to_string()],
+                vec!["12345".to_string(), "-".to_string()],
+            ],
+        };
+
+        assert_eq!(
+            table.to_string(),
+            "id     reason\n-----  -------------\n1      token_expired\n12345  -\n"
+        );
+    }
+}
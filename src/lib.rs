@@ -0,0 +1,5 @@
+#[macro_use]
+extern crate rocket;
+
+pub mod routes;
+pub mod utils;
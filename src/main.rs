@@ -1,108 +1,63 @@
-#[macro_use]
-extern crate rocket;
-pub mod routes;
-pub mod utils;
-
-use crate::{
-    routes::{authorized::authorized, catchers::not_found, healthz::healthz, start::start},
+use annie_mei_auth::{
+    routes::{
+        admin::{
+            audit_history, force_relink, get_credential, list_credentials, metrics,
+            unlink_credential, verify_credential,
+        },
+        authorized::authorized,
+        catchers::{
+            admin_bad_request, admin_default_error, admin_forbidden, admin_internal_error,
+            admin_not_found, admin_unauthorized, admin_unprocessable, bad_request, default_error,
+            forbidden, internal_error, not_found, service_unavailable, too_many_requests,
+            unprocessable_entity,
+        },
+        export::export,
+        healthz::{healthz, livez, readyz},
+        start::{confirm_start, start},
+    },
     utils::{
-        consts::{ANILIST_TOKEN, ANILIST_USER_BASE},
+        anilist_client::AniListClient,
+        config::AppConfig,
+        database::{Database, run_migrations},
+        fairings::{RequestIdHeader, SecurityHeaders},
+        functions::MIGRATOR,
+        lifecycle::{Lifecycle, drain_on_shutdown},
+        metrics::install_prometheus_recorder,
+        observability::RequestIdLayer,
+        pages::Pages,
+        probe::spawn_credential_probe_job,
+        rate_limit::RateLimits,
+        redis_sessions::RedisSessionStore,
+        reload::{ReloadableSettings, SettingsHandle, spawn_reload_on_sighup},
+        retention::spawn_retention_job,
+        sqlite::connect_sqlite,
+        store::{Storage, StorageBackend},
         structs::MyState,
     },
 };
-use rocket::fs::{FileServer, relative};
+use rocket::{
+    catchers,
+    fairing::AdHoc,
+    fs::{FileServer, relative},
+    routes,
+};
 
 use anyhow::{Context, Result};
-use sqlx::postgres::PgPoolOptions;
-use std::env;
-use std::sync::Arc;
+use clap::Parser;
+use std::{path::PathBuf, sync::Arc, time::Duration};
 use tracing_subscriber::prelude::*;
 
-const DEFAULT_CONTEXT_TTL_SECONDS: i64 = 300;
-const DEFAULT_STATE_TTL_SECONDS: i64 = 300;
-
-struct AppConfig {
-    sentry_dsn: Option<String>,
-    sentry_environment: Option<String>,
-    sentry_traces_sample_rate: f32,
-    client_id: String,
-    client_secret: String,
-    redirect_uri: String,
-    context_signing_secret: String,
-    user_id_hash_salt: String,
-    context_ttl_seconds: i64,
-    state_ttl_seconds: i64,
-    database_url: String,
-    rocket_secret_key: String,
-}
-
-impl AppConfig {
-    fn from_env() -> Result<Self> {
-        let _ = dotenvy::dotenv();
-
-        let (sentry_traces_sample_rate, sentry_traces_sample_rate_invalid) =
-            match optional_env("SENTRY_TRACES_SAMPLE_RATE") {
-                Some(raw) => match raw.parse::<f32>() {
-                    Ok(rate) if rate.is_finite() => (rate.clamp(0.0, 1.0), None),
-                    _ => (0.0, Some(raw)),
-                },
-                None => (0.0, None),
-            };
-
-        if let Some(invalid_value) = sentry_traces_sample_rate_invalid {
-            eprintln!("Invalid SENTRY_TRACES_SAMPLE_RATE={invalid_value}; defaulting to 0.0");
-        }
-
-        Ok(Self {
-            sentry_dsn: optional_env("SENTRY_DSN"),
-            sentry_environment: optional_env("SENTRY_ENVIRONMENT"),
-            sentry_traces_sample_rate,
-            client_id: required_env("ANILIST_CLIENT_ID")?,
-            client_secret: required_env("ANILIST_CLIENT_SECRET")?,
-            redirect_uri: required_env("ANILIST_REDIRECT_URI")?,
-            context_signing_secret: required_env("OAUTH_CONTEXT_SIGNING_SECRET")?,
-            user_id_hash_salt: required_env("USERID_HASH_SALT")?,
-            context_ttl_seconds: optional_positive_i64_env("OAUTH_CONTEXT_TTL_SECONDS")?
-                .unwrap_or(DEFAULT_CONTEXT_TTL_SECONDS),
-            state_ttl_seconds: optional_positive_i64_env("OAUTH_STATE_TTL_SECONDS")?
-                .unwrap_or(DEFAULT_STATE_TTL_SECONDS),
-            database_url: required_env("DATABASE_URL")?,
-            rocket_secret_key: required_env("ROCKET_SECRET_KEY")?,
-        })
-    }
-}
-
-fn optional_env(key: &str) -> Option<String> {
-    env::var(key).ok().and_then(non_empty_env_value)
-}
-
-fn non_empty_env_value(value: String) -> Option<String> {
-    if value.trim().is_empty() {
-        None
-    } else {
-        Some(value)
-    }
-}
-
-fn required_env(key: &str) -> Result<String> {
-    let value = env::var(key).with_context(|| format!("{key} was not found"))?;
-
-    non_empty_env_value(value).with_context(|| format!("{key} was empty"))
-}
-
-fn optional_positive_i64_env(key: &str) -> Result<Option<i64>> {
-    let Some(value) = optional_env(key) else {
-        return Ok(None);
-    };
-
-    let parsed = value
-        .parse::<i64>()
-        .with_context(|| format!("{key} must be a positive integer"))?;
-
-    (parsed > 0)
-        .then_some(parsed)
-        .with_context(|| format!("{key} must be a positive integer"))
-        .map(Some)
+/// AniList OAuth server for Annie Mei.
+#[derive(Debug, Parser)]
+#[command(name = "annie-mei-auth", version, about)]
+struct Cli {
+    /// TOML config file; environment variables override its values. Defaults to `CONFIG_FILE`.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Validate the configuration, print it with secrets redacted and exit.
+    #[arg(long)]
+    check_config: bool,
 }
 
 fn init_sentry(
@@ -110,7 +65,9 @@ fn init_sentry(
     environment: Option<String>,
     traces_sample_rate: f32,
 ) -> sentry::ClientInitGuard {
-    use crate::utils::observability::redact_url_credentials;
+    use annie_mei_auth::utils::observability::{
+        REQUEST_ID_TAG, current_request_id, redact_url_credentials,
+    };
 
     sentry::init((
         dsn,
@@ -135,6 +92,12 @@ fn init_sentry(
                         *message = redact_url_credentials(message);
                     }
                 }
+                if let Some(request_id) = current_request_id() {
+                    event
+                        .tags
+                        .entry(REQUEST_ID_TAG.to_string())
+                        .or_insert(request_id);
+                }
                 Some(event)
             })),
             before_send_log: Some(Arc::new(|mut log| {
@@ -146,53 +109,237 @@ fn init_sentry(
     ))
 }
 
-async fn build_rocket(config: &AppConfig) -> Result<rocket::Rocket<rocket::Build>> {
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&config.database_url)
+async fn connect_database(config: &AppConfig) -> Result<Database> {
+    let database_url = config
+        .database_url
+        .as_deref()
+        .context("DATABASE_URL was not found")?;
+    let pool = config
+        .database_pool
+        .connect(database_url)
         .await
         .context("Failed to connect to the database")?;
+    let read_pool = match &config.database_replica_url {
+        Some(url) => config
+            .database_pool
+            .connect(url)
+            .await
+            .context("Failed to connect to the read replica")?,
+        None => pool.clone(),
+    };
 
-    sqlx::migrate!("./migrations")
-        .run(&pool)
+    run_migrations(&MIGRATOR, &pool)
         .await
         .context("Failed to run database migrations")?;
 
-    let client = reqwest::Client::builder()
-        .user_agent(concat!(
-            env!("CARGO_PKG_NAME"),
-            "/",
-            env!("CARGO_PKG_VERSION")
-        ))
-        .build()
-        .context("Failed to build HTTP client")?;
+    Ok(Database { pool, read_pool })
+}
+
+async fn build_rocket(
+    config: &AppConfig,
+    config_file: Option<PathBuf>,
+) -> Result<rocket::Rocket<rocket::Build>> {
+    let (mut storage, database) = match config.storage_backend {
+        StorageBackend::Postgres => {
+            let database = connect_database(config).await?;
+            (Storage::postgres(database.pool.clone()), Some(database))
+        }
+        StorageBackend::Sqlite => {
+            let path = config
+                .sqlite_path
+                .as_deref()
+                .context("SQLITE_PATH was not found")?;
+            let pool = connect_sqlite(path)
+                .await
+                .with_context(|| format!("Failed to open SQLite database {}", path.display()))?;
+            eprintln!(
+                "STORAGE_BACKEND=sqlite; the admin API, exports and background jobs are disabled"
+            );
+            (Storage::sqlite(pool), None)
+        }
+        StorageBackend::Memory => {
+            eprintln!(
+                "STORAGE_BACKEND=memory; links are lost on restart and the admin API, exports and background jobs are disabled"
+            );
+            (Storage::memory(), None)
+        }
+    };
+    if let Some(url) = &config.redis_url {
+        let sessions = RedisSessionStore::connect(url)
+            .await
+            .context("Failed to connect to Redis")?;
+        storage.sessions = Arc::new(sessions);
+    }
+
+    let client = AniListClient::new(config.anilist_http).context("Failed to build HTTP client")?;
+
+    let metrics_handle = install_prometheus_recorder()?;
+
+    let pages =
+        Pages::load(config.template_dir.as_deref()).context("Failed to load HTML templates")?;
 
     let state = MyState {
         client_id: config.client_id.clone(),
-        client_secret: config.client_secret.clone(),
         redirect_uri: config.redirect_uri.clone(),
-        context_signing_secret: config.context_signing_secret.clone(),
         user_id_hash_salt: config.user_id_hash_salt.clone(),
-        context_ttl_seconds: config.context_ttl_seconds,
-        state_ttl_seconds: config.state_ttl_seconds,
-        token_endpoint: ANILIST_TOKEN.to_string(),
-        user_endpoint: ANILIST_USER_BASE.to_string(),
+        token_endpoint: config.token_endpoint.clone(),
+        user_endpoint: config.user_endpoint.clone(),
         client,
-        pool,
+        storage,
+        database,
+        settings: SettingsHandle::new(ReloadableSettings::from(config)),
+        rate_limits: RateLimits::per_minute(
+            config.rate_limit_per_ip_per_minute,
+            config.rate_limit_per_discord_user_per_minute,
+        ),
+        pages,
+        consent_page_enabled: config.consent_page_enabled,
+        lifecycle: Lifecycle::default(),
     };
 
-    let figment = rocket::Config::figment().merge(("secret_key", config.rocket_secret_key.clone()));
+    let figment = rocket::Config::figment()
+        .merge(("secret_key", config.rocket_secret_key.clone()))
+        .merge(("shutdown.grace", config.shutdown_grace_seconds));
+    // Rocket trusts `X-Real-IP` by default, which any client can set.
+    let figment = match &config.client_ip_header {
+        Some(header) => figment.merge(("ip_header", header.clone())),
+        None => figment.merge(("ip_header", false)),
+    };
 
-    Ok(rocket::custom(figment)
-        .mount("/", routes![healthz, start, authorized])
+    let database_pool = state
+        .database
+        .as_ref()
+        .map(|database| database.pool.clone());
+    let probe_client = state.client.clone();
+    let retention_lifecycle = state.lifecycle.clone();
+    let probe_lifecycle = state.lifecycle.clone();
+    let reload_settings = state.settings.clone();
+    let reload_lifecycle = state.lifecycle.clone();
+    let grace = Duration::from_secs(config.shutdown_grace_seconds.into());
+
+    let rocket = rocket::custom(figment)
+        .mount(
+            "/",
+            routes![
+                healthz,
+                livez,
+                readyz,
+                start,
+                confirm_start,
+                authorized,
+                export
+            ],
+        )
+        .mount(
+            "/admin",
+            routes![
+                list_credentials,
+                get_credential,
+                audit_history,
+                force_relink,
+                unlink_credential,
+                verify_credential,
+                metrics
+            ],
+        )
         .mount("/static", FileServer::from(relative!("static")))
-        .register("/", catchers![not_found])
-        .manage(state))
+        .register(
+            "/",
+            catchers![
+                bad_request,
+                forbidden,
+                not_found,
+                unprocessable_entity,
+                too_many_requests,
+                internal_error,
+                service_unavailable,
+                default_error
+            ],
+        )
+        .register(
+            "/admin",
+            catchers![
+                admin_bad_request,
+                admin_unauthorized,
+                admin_forbidden,
+                admin_not_found,
+                admin_unprocessable,
+                admin_internal_error,
+                admin_default_error
+            ],
+        )
+        .attach(RequestIdHeader)
+        .attach(SecurityHeaders)
+        .attach(drain_on_shutdown(grace))
+        .manage(state)
+        .manage(metrics_handle);
+
+    #[cfg(unix)]
+    let rocket = rocket.attach(AdHoc::on_liftoff("Reload settings on SIGHUP", move |_| {
+        Box::pin(async move {
+            if let Err(e) = spawn_reload_on_sighup(config_file, reload_settings, reload_lifecycle) {
+                eprintln!("Failed to listen for SIGHUP; settings will not reload: {e}");
+            }
+        })
+    }));
+
+    let rocket = match database_pool.clone() {
+        Some(retention_pool) if config.retention_enabled => {
+            let policy = config.retention;
+            let interval = Duration::from_secs(config.retention_interval_seconds.unsigned_abs());
+            let salt = config.user_id_hash_salt.clone();
+            rocket.attach(AdHoc::on_liftoff("Retention job", move |_| {
+                Box::pin(async move {
+                    spawn_retention_job(policy, interval, salt, retention_pool, retention_lifecycle)
+                })
+            }))
+        }
+        Some(_) => {
+            eprintln!("RETENTION_ENABLED=false; retention policies are not enforced");
+            rocket
+        }
+        None => rocket,
+    };
+
+    if !config.credential_probe_enabled {
+        return Ok(rocket);
+    }
+    let Some(probe_pool) = database_pool else {
+        return Ok(rocket);
+    };
+
+    let settings = config.credential_probe;
+    let interval = Duration::from_secs(config.credential_probe_interval_seconds.unsigned_abs());
+    let salt = config.user_id_hash_salt.clone();
+    let user_endpoint = config.user_endpoint.clone();
+    Ok(
+        rocket.attach(AdHoc::on_liftoff("Credential probe job", move |_| {
+            Box::pin(async move {
+                spawn_credential_probe_job(
+                    settings,
+                    interval,
+                    probe_client,
+                    user_endpoint,
+                    salt,
+                    probe_pool,
+                    probe_lifecycle,
+                )
+            })
+        })),
+    )
 }
 
 #[rocket::main]
 async fn main() -> Result<()> {
-    let config = AppConfig::from_env()?;
+    let cli = Cli::parse();
+    let config = AppConfig::load(cli.config.as_deref())?;
+    if cli.check_config {
+        Pages::load(config.template_dir.as_deref()).context("Failed to load HTML templates")?;
+        print!("{}", config.redacted_summary());
+        eprintln!("Configuration is valid");
+        return Ok(());
+    }
+
     let _sentry = config.sentry_dsn.as_deref().map(|dsn| {
         init_sentry(
             dsn,
@@ -202,6 +349,7 @@ async fn main() -> Result<()> {
     });
 
     tracing_subscriber::registry()
+        .with(RequestIdLayer)
         .with(sentry::integrations::tracing::layer())
         .init();
 
@@ -214,49 +362,14 @@ async fn main() -> Result<()> {
         );
     }
 
-    build_rocket(&config).await?.launch().await?;
-
-    Ok(())
-}
-
-#[cfg(test)]
-mod tests {
-    use super::{non_empty_env_value, optional_positive_i64_env, required_env};
-    use std::env;
-
-    #[test]
-    fn non_empty_env_value_rejects_blank_strings() {
-        assert_eq!(non_empty_env_value(String::new()), None);
-        assert_eq!(non_empty_env_value("   ".to_string()), None);
-        assert_eq!(
-            non_empty_env_value("dsn".to_string()),
-            Some("dsn".to_string())
-        );
-    }
-
-    #[test]
-    fn required_env_rejects_blank_values() {
-        let key = "ANNIE_MEI_AUTH_REQUIRED_ENV_TEST";
-
-        unsafe { env::set_var(key, "   ") };
-        let error = required_env(key).expect_err("blank env vars should fail validation");
-        assert!(error.to_string().contains("was empty"));
-
-        unsafe { env::set_var(key, "value") };
-        let value = required_env(key).expect("non-empty env vars should pass validation");
-        assert_eq!(value, "value");
-
-        unsafe { env::remove_var(key) };
+    let rocket = build_rocket(&config, cli.config).await?.launch().await?;
+    if let Some(database) = rocket
+        .state::<MyState>()
+        .and_then(|state| state.database.as_ref())
+    {
+        database.pool.close().await;
+        database.read_pool.close().await;
     }
 
-    #[test]
-    fn optional_positive_i64_env_rejects_zero() {
-        let key = "ANNIE_MEI_AUTH_OPTIONAL_INT_TEST";
-
-        unsafe { env::set_var(key, "0") };
-        let error = optional_positive_i64_env(key).expect_err("zero should fail validation");
-        assert!(error.to_string().contains("positive integer"));
-
-        unsafe { env::remove_var(key) };
-    }
+    Ok(())
 }
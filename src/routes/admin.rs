@@ -0,0 +1,868 @@
+use crate::utils::{
+    database::Database,
+    functions::{
+        CredentialVerificationError, fetch_audit_events, fetch_credential_by_discord_user,
+        force_relink_oauth_credential, search_credentials, unlink_oauth_credential,
+        verify_oauth_credential,
+    },
+    observability::{configure_oauth_scope, identifier_fingerprint},
+    structs::{
+        AdminErrorBody, AdminRead, AdminWrite, AuditEvent, CredentialHealth, CredentialSearch,
+        CredentialSummary, MyState, RequestId,
+    },
+};
+
+use metrics_exporter_prometheus::PrometheusHandle;
+use rocket::{
+    State,
+    form::{FromFormField, ValueField},
+    http::{ContentType, Status},
+    response::status::Custom,
+    serde::{Deserialize, Serialize, json::Json},
+};
+
+const DEFAULT_PAGE_LIMIT: i64 = 50;
+const MAX_PAGE_LIMIT: i64 = 500;
+const MAX_RELINK_REASON_LENGTH: usize = 200;
+
+type AdminResult<T> = Result<Json<T>, Custom<Json<AdminErrorBody>>>;
+
+#[derive(Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct RelinkRequest {
+    reason: String,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct UnlinkResponse {
+    discord_user_id: String,
+    unlinked: bool,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct VerifyResponse {
+    discord_user_id: String,
+    status: CredentialHealth,
+}
+
+fn admin_error(status: Status, error: &'static str) -> Custom<Json<AdminErrorBody>> {
+    Custom(status, Json(AdminErrorBody { error }))
+}
+
+fn database_error(operation: &str, error: &sqlx::Error) -> Custom<Json<AdminErrorBody>> {
+    sentry::with_scope(
+        |scope| configure_oauth_scope(scope, operation, None),
+        || sentry::capture_error(error),
+    );
+    error!("Admin API database operation failed ({operation})");
+    admin_error(Status::InternalServerError, "internal_error")
+}
+
+/// The admin API works on the Postgres tables directly, so it is unavailable with the
+/// SQLite and in-memory stores.
+fn database(state: &MyState) -> Result<&Database, Custom<Json<AdminErrorBody>>> {
+    state
+        .database
+        .as_ref()
+        .ok_or_else(|| admin_error(Status::ServiceUnavailable, "database_unavailable"))
+}
+
+fn page_limit(limit: Option<i64>) -> Result<i64, Custom<Json<AdminErrorBody>>> {
+    match limit {
+        None => Ok(DEFAULT_PAGE_LIMIT),
+        Some(limit) if (1..=MAX_PAGE_LIMIT).contains(&limit) => Ok(limit),
+        Some(_) => Err(admin_error(Status::BadRequest, "invalid_limit")),
+    }
+}
+
+/// Rocket turns a malformed `Option<T>` query value into `None`, which would silently
+/// drop a search filter. Filters are taken as text and parsed here instead.
+fn query_param<'v, T: FromFormField<'v>>(
+    value: Option<&'v str>,
+) -> Result<Option<T>, Custom<Json<AdminErrorBody>>> {
+    value
+        .map(|raw| T::from_value(ValueField::from_value(raw)))
+        .transpose()
+        .map_err(|_| admin_error(Status::UnprocessableEntity, "invalid_query"))
+}
+
+fn admin_actor(token_name: &str) -> String {
+    format!("admin:{token_name}")
+}
+
+#[get("/credentials?<discord_user_id>&<anilist_id>&<relink_required>&<limit>")]
+#[tracing::instrument(name = "admin.search_credentials", skip_all, fields(admin_token = %admin.0, request_id = %request_id.0))]
+pub async fn list_credentials(
+    admin: AdminRead,
+    request_id: RequestId,
+    discord_user_id: Option<&str>,
+    anilist_id: Option<&str>,
+    relink_required: Option<&str>,
+    limit: Option<&str>,
+    state: &State<MyState>,
+) -> AdminResult<Vec<CredentialSummary>> {
+    let search = CredentialSearch {
+        discord_user_id: discord_user_id.map(str::to_string),
+        anilist_id: query_param(anilist_id)?,
+        relink_required: query_param(relink_required)?,
+        limit: page_limit(query_param(limit)?)?,
+    };
+    let database = database(state)?;
+
+    let credentials = search_credentials(&search, &database.read_pool)
+        .await
+        .map_err(|error| database_error("admin.search_credentials", &error))?;
+
+    Ok(Json(
+        credentials.iter().map(CredentialSummary::from).collect(),
+    ))
+}
+
+#[get("/credentials/<discord_user_id>")]
+#[tracing::instrument(name = "admin.get_credential", skip_all, fields(admin_token = %admin.0, request_id = %request_id.0))]
+pub async fn get_credential(
+    admin: AdminRead,
+    request_id: RequestId,
+    discord_user_id: &str,
+    state: &State<MyState>,
+) -> AdminResult<CredentialSummary> {
+    let database = database(state)?;
+    let credential = fetch_credential_by_discord_user(
+        discord_user_id,
+        &state.user_id_hash_salt,
+        &database.read_pool,
+    )
+    .await
+    .map_err(|error| database_error("admin.get_credential", &error))?
+    .ok_or_else(|| admin_error(Status::NotFound, "credential_not_found"))?;
+
+    Ok(Json(CredentialSummary::from(&credential)))
+}
+
+#[get("/credentials/<discord_user_id>/audit?<limit>")]
+#[tracing::instrument(name = "admin.audit_history", skip_all, fields(admin_token = %admin.0, request_id = %request_id.0))]
+pub async fn audit_history(
+    admin: AdminRead,
+    request_id: RequestId,
+    discord_user_id: &str,
+    limit: Option<&str>,
+    state: &State<MyState>,
+) -> AdminResult<Vec<AuditEvent>> {
+    let limit = page_limit(query_param(limit)?)?;
+    let database = database(state)?;
+    let events = fetch_audit_events(
+        discord_user_id,
+        limit,
+        &state.user_id_hash_salt,
+        &database.read_pool,
+    )
+    .await
+    .map_err(|error| database_error("admin.audit_history", &error))?;
+
+    Ok(Json(events))
+}
+
+#[post("/credentials/<discord_user_id>/relink", data = "<request>")]
+#[tracing::instrument(
+    name = "admin.force_relink",
+    skip_all,
+    fields(admin_token = %admin.0, request_id = %request_id.0, discord_user_fingerprint = tracing::field::Empty)
+)]
+pub async fn force_relink(
+    admin: AdminWrite,
+    request_id: RequestId,
+    discord_user_id: &str,
+    request: Json<RelinkRequest>,
+    state: &State<MyState>,
+) -> AdminResult<CredentialSummary> {
+    tracing::Span::current().record(
+        "discord_user_fingerprint",
+        identifier_fingerprint(discord_user_id, &state.user_id_hash_salt),
+    );
+
+    let reason = request.reason.trim();
+    if reason.is_empty() || reason.len() > MAX_RELINK_REASON_LENGTH {
+        return Err(admin_error(Status::BadRequest, "invalid_reason"));
+    }
+    let database = database(state)?;
+
+    let credential = force_relink_oauth_credential(
+        discord_user_id,
+        reason,
+        &admin_actor(&admin.0),
+        &state.user_id_hash_salt,
+        &database.pool,
+    )
+    .await
+    .map_err(|error| database_error("admin.force_relink", &error))?
+    .ok_or_else(|| admin_error(Status::NotFound, "credential_not_found"))?;
+
+    info!("Admin forced AniList relink");
+    Ok(Json(CredentialSummary::from(&credential)))
+}
+
+#[delete("/credentials/<discord_user_id>")]
+#[tracing::instrument(
+    name = "admin.unlink",
+    skip_all,
+    fields(admin_token = %admin.0, request_id = %request_id.0, discord_user_fingerprint = tracing::field::Empty)
+)]
+pub async fn unlink_credential(
+    admin: AdminWrite,
+    request_id: RequestId,
+    discord_user_id: &str,
+    state: &State<MyState>,
+) -> AdminResult<UnlinkResponse> {
+    tracing::Span::current().record(
+        "discord_user_fingerprint",
+        identifier_fingerprint(discord_user_id, &state.user_id_hash_salt),
+    );
+    let database = database(state)?;
+
+    unlink_oauth_credential(
+        discord_user_id,
+        &admin_actor(&admin.0),
+        &state.user_id_hash_salt,
+        &database.pool,
+    )
+    .await
+    .map_err(|error| database_error("admin.unlink", &error))?
+    .ok_or_else(|| admin_error(Status::NotFound, "credential_not_found"))?;
+
+    info!("Admin unlinked AniList account");
+    Ok(Json(UnlinkResponse {
+        discord_user_id: discord_user_id.to_string(),
+        unlinked: true,
+    }))
+}
+
+#[post("/credentials/<discord_user_id>/verify")]
+#[tracing::instrument(
+    name = "admin.verify_credential",
+    skip_all,
+    fields(admin_token = %admin.0, request_id = %request_id.0, discord_user_fingerprint = tracing::field::Empty)
+)]
+pub async fn verify_credential(
+    admin: AdminWrite,
+    request_id: RequestId,
+    discord_user_id: &str,
+    state: &State<MyState>,
+) -> AdminResult<VerifyResponse> {
+    tracing::Span::current().record(
+        "discord_user_fingerprint",
+        identifier_fingerprint(discord_user_id, &state.user_id_hash_salt),
+    );
+    let database = database(state)?;
+
+    let status = verify_oauth_credential(
+        &state.client,
+        &state.user_endpoint,
+        discord_user_id,
+        &admin_actor(&admin.0),
+        &state.user_id_hash_salt,
+        &database.pool,
+    )
+    .await
+    .map_err(|error| match error {
+        CredentialVerificationError::Missing => {
+            admin_error(Status::NotFound, "credential_not_found")
+        }
+        CredentialVerificationError::Upstream(_) => {
+            admin_error(Status::BadGateway, "upstream_unavailable")
+        }
+        CredentialVerificationError::Db(error) => database_error("admin.verify_credential", &error),
+    })?;
+
+    info!("Admin verified AniList credential");
+    Ok(Json(VerifyResponse {
+        discord_user_id: discord_user_id.to_string(),
+        status,
+    }))
+}
+
+#[get("/metrics")]
+#[tracing::instrument(name = "admin.metrics", skip_all, fields(admin_token = %admin.0, request_id = %request_id.0))]
+pub fn metrics(
+    admin: AdminRead,
+    request_id: RequestId,
+    handle: &State<PrometheusHandle>,
+) -> (ContentType, String) {
+    (ContentType::Plain, handle.render())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        audit_history, force_relink, get_credential, list_credentials, metrics, unlink_credential,
+        verify_credential,
+    };
+    use crate::{
+        routes::catchers::{
+            admin_bad_request, admin_default_error, admin_forbidden, admin_internal_error,
+            admin_not_found, admin_unauthorized, admin_unprocessable,
+        },
+        utils::{
+            anilist_client::AniListClient,
+            database::Database,
+            functions::{fetch_credential_by_discord_user, upsert_oauth_credentials},
+            lifecycle::Lifecycle,
+            pages::Pages,
+            rate_limit::RateLimits,
+            reload::{ReloadableSettings, SettingsHandle},
+            store::Storage,
+            structs::{AdminApiToken, AdminScope, MyState},
+        },
+    };
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use rocket::{
+        Config, catchers,
+        http::{ContentType, Header, Status},
+        local::asynchronous::Client,
+        routes,
+    };
+    use sqlx::{Pool, Postgres, postgres::PgPoolOptions};
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path},
+    };
+
+    const TEST_USERID_HASH_SALT: &str = "test-userid-hash-salt";
+    const READ_TOKEN: &str = "read-token-0123456789abcdef012345";
+    const WRITE_TOKEN: &str = "write-token-0123456789abcdef01234";
+
+    fn build_test_rocket(pool: Pool<Postgres>) -> rocket::Rocket<rocket::Build> {
+        build_test_rocket_with_user_endpoint(pool, "https://graphql.anilist.co")
+    }
+
+    fn build_test_rocket_with_user_endpoint(
+        pool: Pool<Postgres>,
+        user_endpoint: &str,
+    ) -> rocket::Rocket<rocket::Build> {
+        build_test_rocket_with_pools(pool.clone(), pool, user_endpoint)
+    }
+
+    fn build_test_rocket_with_pools(
+        pool: Pool<Postgres>,
+        read_pool: Pool<Postgres>,
+        user_endpoint: &str,
+    ) -> rocket::Rocket<rocket::Build> {
+        let figment =
+            Config::figment().merge(("secret_key", "0123456789abcdef0123456789abcdef0123456789A="));
+
+        let state = MyState {
+            client_id: "client-id".to_string(),
+            redirect_uri: "http://127.0.0.1:8000/oauth/anilist/callback".to_string(),
+            user_id_hash_salt: TEST_USERID_HASH_SALT.to_string(),
+            token_endpoint: "https://anilist.co/api/v2/oauth/token".to_string(),
+            user_endpoint: user_endpoint.to_string(),
+            client: AniListClient::default(),
+            storage: Storage::postgres(pool.clone()),
+            database: Some(Database { pool, read_pool }),
+            settings: SettingsHandle::new(ReloadableSettings {
+                client_secret: "client-secret".to_string(),
+                context_signing_secret: "context-signing-secret".to_string(),
+                context_ttl_seconds: 300,
+                state_ttl_seconds: 600,
+                admin_tokens: vec![
+                    AdminApiToken::new(
+                        "dashboard-read".to_string(),
+                        vec![AdminScope::Read],
+                        READ_TOKEN,
+                    ),
+                    AdminApiToken::new(
+                        "dashboard".to_string(),
+                        vec![AdminScope::Read, AdminScope::Write],
+                        WRITE_TOKEN,
+                    ),
+                ],
+            }),
+            rate_limits: RateLimits::per_minute(1_000, 1_000),
+            pages: Pages::embedded().clone(),
+            consent_page_enabled: false,
+            lifecycle: Lifecycle::default(),
+        };
+
+        rocket::custom(figment)
+            .mount(
+                "/admin",
+                routes![
+                    list_credentials,
+                    get_credential,
+                    audit_history,
+                    force_relink,
+                    unlink_credential,
+                    verify_credential,
+                    metrics
+                ],
+            )
+            .register(
+                "/admin",
+                catchers![
+                    admin_bad_request,
+                    admin_unauthorized,
+                    admin_forbidden,
+                    admin_not_found,
+                    admin_unprocessable,
+                    admin_internal_error,
+                    admin_default_error
+                ],
+            )
+            .manage(state)
+            .manage(PrometheusBuilder::new().build_recorder().handle())
+    }
+
+    fn bearer(token: &str) -> Header<'static> {
+        Header::new("Authorization", format!("Bearer {token}"))
+    }
+
+    async fn seed_credential(pool: &Pool<Postgres>) {
+        upsert_oauth_credentials(
+            "admin_target",
+            4242,
+            "raw-access-token-never-returned",
+            Some("raw-refresh-token-never-returned"),
+            None,
+            TEST_USERID_HASH_SALT,
+            pool,
+        )
+        .await
+        .expect("seed upsert should succeed");
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn admin_routes_require_bearer_token(pool: Pool<Postgres>) {
+        let client = Client::tracked(build_test_rocket(pool.clone()))
+            .await
+            .expect("rocket client should build");
+
+        let missing = client.get("/admin/credentials").dispatch().await;
+        assert_eq!(missing.status(), Status::Unauthorized);
+        assert_eq!(
+            missing.headers().get_one("WWW-Authenticate"),
+            Some("Bearer")
+        );
+        assert_eq!(missing.content_type(), Some(ContentType::JSON));
+        drop(missing);
+
+        let wrong = client
+            .get("/admin/credentials")
+            .header(bearer("not-a-configured-token"))
+            .dispatch()
+            .await;
+        assert_eq!(wrong.status(), Status::Unauthorized);
+        drop(wrong);
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn write_routes_reject_read_only_tokens(pool: Pool<Postgres>) {
+        seed_credential(&pool).await;
+        let client = Client::tracked(build_test_rocket(pool.clone()))
+            .await
+            .expect("rocket client should build");
+
+        let response = client
+            .delete("/admin/credentials/admin_target")
+            .header(bearer(READ_TOKEN))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Forbidden);
+        let body = response.into_string().await.expect("body should exist");
+        assert!(body.contains("forbidden"));
+
+        let credential =
+            fetch_credential_by_discord_user("admin_target", TEST_USERID_HASH_SALT, &pool)
+                .await
+                .expect("fetch should not error");
+        assert!(credential.is_some());
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn search_masks_tokens(pool: Pool<Postgres>) {
+        seed_credential(&pool).await;
+        let client = Client::tracked(build_test_rocket(pool.clone()))
+            .await
+            .expect("rocket client should build");
+
+        let response = client
+            .get("/admin/credentials?anilist_id=4242")
+            .header(bearer(READ_TOKEN))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let body = response.into_string().await.expect("body should exist");
+        assert!(body.contains("admin_target"));
+        assert!(body.contains("********"));
+        assert!(!body.contains("raw-access-token"));
+        assert!(!body.contains("raw-refresh-token"));
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn queries_reject_malformed_filters(pool: Pool<Postgres>) {
+        seed_credential(&pool).await;
+        let client = Client::tracked(build_test_rocket(pool.clone()))
+            .await
+            .expect("rocket client should build");
+
+        for query in ["anilist_id=abc", "relink_required=maybe", "limit=ten"] {
+            let response = client
+                .get(format!("/admin/credentials?{query}"))
+                .header(bearer(READ_TOKEN))
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), Status::UnprocessableEntity, "{query}");
+            let body = response.into_string().await.expect("body should exist");
+            assert!(body.contains("\"error\":\"invalid_query\""), "{query}");
+            assert!(!body.contains("admin_target"), "{query}");
+        }
+
+        let audit = client
+            .get("/admin/credentials/admin_target/audit?limit=abc")
+            .header(bearer(READ_TOKEN))
+            .dispatch()
+            .await;
+        assert_eq!(audit.status(), Status::UnprocessableEntity);
+        let body = audit.into_string().await.expect("body should exist");
+        assert!(body.contains("\"error\":\"invalid_query\""));
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn force_relink_is_audited(pool: Pool<Postgres>) {
+        seed_credential(&pool).await;
+        let client = Client::tracked(build_test_rocket(pool.clone()))
+            .await
+            .expect("rocket client should build");
+
+        let response = client
+            .post("/admin/credentials/admin_target/relink")
+            .header(bearer(WRITE_TOKEN))
+            .header(ContentType::JSON)
+            .body(r#"{"reason":"support_ticket"}"#)
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        drop(response);
+
+        let credential =
+            fetch_credential_by_discord_user("admin_target", TEST_USERID_HASH_SALT, &pool)
+                .await
+                .expect("fetch should not error")
+                .expect("credential should exist");
+        assert_eq!(credential.relink_reason.as_deref(), Some("support_ticket"));
+
+        let audit = client
+            .get("/admin/credentials/admin_target/audit")
+            .header(bearer(READ_TOKEN))
+            .dispatch()
+            .await;
+        assert_eq!(audit.status(), Status::Ok);
+        let body = audit.into_string().await.expect("body should exist");
+        assert!(body.contains("\"event\":\"relink_required\""));
+        assert!(body.contains("\"actor\":\"admin:dashboard\""));
+
+        let missing = client
+            .post("/admin/credentials/nobody/relink")
+            .header(bearer(WRITE_TOKEN))
+            .header(ContentType::JSON)
+            .body(r#"{"reason":"support_ticket"}"#)
+            .dispatch()
+            .await;
+        assert_eq!(missing.status(), Status::NotFound);
+        drop(missing);
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn malformed_bodies_get_admin_error_json(pool: Pool<Postgres>) {
+        seed_credential(&pool).await;
+        let client = Client::tracked(build_test_rocket(pool.clone()))
+            .await
+            .expect("rocket client should build");
+
+        for (body, status, error) in [
+            ("{not json", Status::BadRequest, "bad_request"),
+            (
+                r#"{"why":"support_ticket"}"#,
+                Status::UnprocessableEntity,
+                "invalid_request_body",
+            ),
+        ] {
+            let response = client
+                .post("/admin/credentials/admin_target/relink")
+                .header(bearer(WRITE_TOKEN))
+                .header(ContentType::JSON)
+                .body(body)
+                .dispatch()
+                .await;
+
+            assert_eq!(response.status(), status, "{body}");
+            assert_eq!(response.content_type(), Some(ContentType::JSON), "{body}");
+            let response_body = response.into_string().await.expect("body should exist");
+            assert_eq!(response_body, format!(r#"{{"error":"{error}"}}"#), "{body}");
+        }
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn lookups_use_read_pool_and_writes_use_primary(pool: Pool<Postgres>) {
+        seed_credential(&pool).await;
+        let read_pool = PgPoolOptions::new()
+            .connect_with(pool.connect_options().as_ref().clone())
+            .await
+            .expect("read pool should connect");
+        read_pool.close().await;
+
+        let client = Client::tracked(build_test_rocket_with_pools(
+            pool.clone(),
+            read_pool,
+            "https://graphql.anilist.co",
+        ))
+        .await
+        .expect("rocket client should build");
+
+        let lookup = client
+            .get("/admin/credentials/admin_target")
+            .header(bearer(READ_TOKEN))
+            .dispatch()
+            .await;
+        assert_eq!(lookup.status(), Status::InternalServerError);
+        drop(lookup);
+
+        let relink = client
+            .post("/admin/credentials/admin_target/relink")
+            .header(bearer(WRITE_TOKEN))
+            .header(ContentType::JSON)
+            .body(r#"{"reason":"support_request"}"#)
+            .dispatch()
+            .await;
+        assert_eq!(relink.status(), Status::Ok);
+        drop(relink);
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn unlink_removes_credential(pool: Pool<Postgres>) {
+        seed_credential(&pool).await;
+        let client = Client::tracked(build_test_rocket(pool.clone()))
+            .await
+            .expect("rocket client should build");
+
+        let response = client
+            .delete("/admin/credentials/admin_target")
+            .header(bearer(WRITE_TOKEN))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let body = response.into_string().await.expect("body should exist");
+        assert!(body.contains("\"unlinked\":true"));
+
+        let credential =
+            fetch_credential_by_discord_user("admin_target", TEST_USERID_HASH_SALT, &pool)
+                .await
+                .expect("fetch should not error");
+        assert!(credential.is_none());
+
+        let audit = client
+            .get("/admin/credentials/admin_target/audit")
+            .header(bearer(READ_TOKEN))
+            .dispatch()
+            .await;
+        let audit_body = audit.into_string().await.expect("body should exist");
+        assert!(audit_body.contains(r#""event":"unlinked""#));
+        assert!(audit_body.contains(r#""anilist_id":4242"#));
+
+        let again = client
+            .delete("/admin/credentials/admin_target")
+            .header(bearer(WRITE_TOKEN))
+            .dispatch()
+            .await;
+        assert_eq!(again.status(), Status::NotFound);
+        drop(again);
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn admin_changes_roll_back_when_the_audit_insert_fails(pool: Pool<Postgres>) {
+        seed_credential(&pool).await;
+        sqlx::query(
+            "ALTER TABLE oauth_audit_events \
+             ADD CONSTRAINT reject_admin CHECK (actor <> 'admin:dashboard')",
+        )
+        .execute(&pool)
+        .await
+        .expect("constraint should be added");
+        let client = Client::tracked(build_test_rocket(pool.clone()))
+            .await
+            .expect("rocket client should build");
+
+        let relink = client
+            .post("/admin/credentials/admin_target/relink")
+            .header(bearer(WRITE_TOKEN))
+            .header(ContentType::JSON)
+            .body(r#"{"reason":"support_ticket"}"#)
+            .dispatch()
+            .await;
+        assert_eq!(relink.status(), Status::InternalServerError);
+        drop(relink);
+
+        let unlink = client
+            .delete("/admin/credentials/admin_target")
+            .header(bearer(WRITE_TOKEN))
+            .dispatch()
+            .await;
+        assert_eq!(unlink.status(), Status::InternalServerError);
+        drop(unlink);
+
+        let credential =
+            fetch_credential_by_discord_user("admin_target", TEST_USERID_HASH_SALT, &pool)
+                .await
+                .expect("fetch should not error")
+                .expect("credential should survive a failed audit insert");
+        assert!(credential.relink_required_at.is_none());
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn metrics_requires_read_token(pool: Pool<Postgres>) {
+        let client = Client::tracked(build_test_rocket(pool.clone()))
+            .await
+            .expect("rocket client should build");
+
+        let anonymous = client.get("/admin/metrics").dispatch().await;
+        assert_eq!(anonymous.status(), Status::Unauthorized);
+        drop(anonymous);
+
+        let response = client
+            .get("/admin/metrics")
+            .header(bearer(READ_TOKEN))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::Plain));
+
+        drop(response);
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn verify_marks_revoked_token_relink_required(pool: Pool<Postgres>) {
+        seed_credential(&pool).await;
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(ResponseTemplate::new(401))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let client = Client::tracked(build_test_rocket_with_user_endpoint(
+            pool.clone(),
+            &format!("{}/graphql", mock_server.uri()),
+        ))
+        .await
+        .expect("rocket client should build");
+
+        let response = client
+            .post("/admin/credentials/admin_target/verify")
+            .header(bearer(WRITE_TOKEN))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let body = response.into_string().await.expect("body should exist");
+        assert!(body.contains(r#""status":"token_revoked""#));
+
+        let credential =
+            fetch_credential_by_discord_user("admin_target", TEST_USERID_HASH_SALT, &pool)
+                .await
+                .expect("fetch should not error")
+                .expect("credential should be kept");
+        assert!(credential.relink_required_at.is_some());
+        assert_eq!(credential.relink_reason.as_deref(), Some("token_revoked"));
+
+        let audit = client
+            .get("/admin/credentials/admin_target/audit")
+            .header(bearer(READ_TOKEN))
+            .dispatch()
+            .await;
+        let audit_body = audit.into_string().await.expect("body should exist");
+        assert!(audit_body.contains(r#""event":"relink_required""#));
+        assert!(audit_body.contains(r#""actor":"admin:dashboard""#));
+        assert!(audit_body.contains("token_revoked"));
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn verify_keeps_accepted_token(pool: Pool<Postgres>) {
+        seed_credential(&pool).await;
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": { "Viewer": { "id": 4242 } }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let client = Client::tracked(build_test_rocket_with_user_endpoint(
+            pool.clone(),
+            &format!("{}/graphql", mock_server.uri()),
+        ))
+        .await
+        .expect("rocket client should build");
+
+        let response = client
+            .post("/admin/credentials/admin_target/verify")
+            .header(bearer(WRITE_TOKEN))
+            .dispatch()
+            .await;
+        assert_eq!(response.status(), Status::Ok);
+        let body = response.into_string().await.expect("body should exist");
+        assert!(body.contains(r#""status":"valid""#));
+
+        let credential =
+            fetch_credential_by_discord_user("admin_target", TEST_USERID_HASH_SALT, &pool)
+                .await
+                .expect("fetch should not error")
+                .expect("credential should exist");
+        assert!(credential.relink_required_at.is_none());
+
+        let missing = client
+            .post("/admin/credentials/nobody/verify")
+            .header(bearer(WRITE_TOKEN))
+            .dispatch()
+            .await;
+        assert_eq!(missing.status(), Status::NotFound);
+
+        drop(missing);
+        drop(client);
+        pool.close().await;
+    }
+}
@@ -1,10 +1,11 @@
 use crate::utils::{
     functions::{
-        UpsertOAuthCredentialsError, exchange_code_for_token, fetch_viewer_id, token_expires_at,
-        upsert_oauth_credentials,
+        AUDIT_ACTOR_USER, AUDIT_EVENT_LINKED, UpsertOAuthCredentialsError, exchange_code_for_token,
+        fetch_viewer_id, token_expires_at,
     },
+    i18n::MessageKey,
     observability::{configure_oauth_scope, identifier_fingerprint, record_identifier_fingerprint},
-    structs::{MyState, StateToken, StateTokenError},
+    structs::{ClientRateLimit, MyState, PageContext, StateToken, StateTokenError},
 };
 
 use rocket::{
@@ -18,6 +19,7 @@ use rocket::{
     name = "oauth.callback",
     skip_all,
     fields(
+        request_id = %page.request_id.0,
         discord_user_fingerprint = tracing::field::Empty,
         anilist_fingerprint = tracing::field::Empty,
         oauth_error_code = tracing::field::Empty
@@ -27,16 +29,26 @@ pub async fn authorized(
     code: Option<&str>,
     error: Option<&str>,
     error_description: Option<&str>,
+    _rate_limit: ClientRateLimit,
+    mut page: PageContext,
     state_token: Result<StateToken, StateTokenError>,
     state: &State<MyState>,
 ) -> Custom<RawHtml<String>> {
+    // Shutdown waits for this so the single-use code is not lost between exchange and save.
+    let _in_flight = state.lifecycle.track();
     let span = tracing::Span::current();
     let state_token = match state_token {
         Ok(state_token) => state_token,
-        Err(error) => return callback_error_for_state_token(error),
+        Err(error) => return callback_error_for_state_token(&page, error),
     };
 
-    let discord_user_fingerprint = identifier_fingerprint(&state_token.0, &state.user_id_hash_salt);
+    // The Discord interaction locale captured at start wins over the browser's preference.
+    if let Some(locale) = state_token.locale {
+        page.locale = locale;
+    }
+
+    let discord_user_fingerprint =
+        identifier_fingerprint(&state_token.discord_user_id, &state.user_id_hash_salt);
     span.record("discord_user_fingerprint", &discord_user_fingerprint);
     info!("State token validated; beginning AniList token exchange");
 
@@ -47,16 +59,17 @@ pub async fn authorized(
             "AniList callback returned an OAuth error (code: {error_code}, has_description: {has_error_description})"
         );
         let message = match error_code {
-            "access_denied" => "Authorization was denied on AniList. Please try again.",
-            _ => "AniList authorization failed. Please try again.",
+            "access_denied" => MessageKey::AuthorizationDenied,
+            _ => MessageKey::AuthorizationFailed,
         };
 
-        return callback_error(message, Status::BadRequest);
+        return callback_error(&page, message, Status::BadRequest);
     }
 
     let Some(code) = code else {
         return callback_error(
-            "Authorization code is missing from the callback.",
+            &page,
+            MessageKey::AuthorizationCodeMissing,
             Status::BadRequest,
         );
     };
@@ -65,7 +78,7 @@ pub async fn authorized(
         &state.client,
         state.token_endpoint.as_str(),
         state.client_id.as_str(),
-        state.client_secret.as_str(),
+        state.settings.current().client_secret.as_str(),
         state.redirect_uri.as_str(),
         code,
         Some(discord_user_fingerprint.as_str()),
@@ -73,7 +86,7 @@ pub async fn authorized(
     .await
     {
         Ok(response) => response,
-        Err(error) => return callback_error(error.message(), error.status()),
+        Err(error) => return callback_error(&page, error.message_key(), error.status()),
     };
 
     let token_expires_at = token_expires_at(token_response.expires_in);
@@ -96,26 +109,27 @@ pub async fn authorized(
             );
             user_id
         }
-        Err(error) => return callback_error(error.message(), error.status()),
+        Err(error) => return callback_error(&page, error.message_key(), error.status()),
     };
     info!("User data fetched successfully");
 
-    if let Err(error) = upsert_oauth_credentials(
-        &state_token.0,
-        anilist_id,
-        &token_response.access_token,
-        token_response.refresh_token.as_deref(),
-        token_expires_at,
-        state.user_id_hash_salt.as_str(),
-        &state.pool,
-    )
-    .await
+    if let Err(error) = state
+        .storage
+        .credentials
+        .upsert_oauth_credentials(
+            &state_token.discord_user_id,
+            anilist_id,
+            &token_response.access_token,
+            token_response.refresh_token.as_deref(),
+            token_expires_at,
+            state.user_id_hash_salt.as_str(),
+        )
+        .await
     {
         return match error {
-            UpsertOAuthCredentialsError::AlreadyLinked => callback_error(
-                "This AniList account is already linked to another Discord user.",
-                Status::BadRequest,
-            ),
+            UpsertOAuthCredentialsError::AlreadyLinked => {
+                callback_error(&page, MessageKey::AlreadyLinked, Status::BadRequest)
+            }
             UpsertOAuthCredentialsError::Db(error) => {
                 sentry::with_scope(
                     |scope| {
@@ -129,7 +143,8 @@ pub async fn authorized(
                 );
                 error!("Failed to persist AniList credentials");
                 callback_error(
-                    "Failed to save AniList credentials. Please retry.",
+                    &page,
+                    MessageKey::SaveCredentialsFailed,
                     Status::InternalServerError,
                 )
             }
@@ -137,166 +152,127 @@ pub async fn authorized(
     }
 
     info!("Saved OAuth credentials for Discord user");
-    callback_success("AniList account connected successfully.")
+
+    // The link itself succeeded; a missing audit row should not turn that into an error page.
+    if let Err(error) = state
+        .storage
+        .credentials
+        .record_audit_event(
+            &state_token.discord_user_id,
+            Some(anilist_id),
+            AUDIT_EVENT_LINKED,
+            AUDIT_ACTOR_USER,
+            None,
+            state.user_id_hash_salt.as_str(),
+        )
+        .await
+    {
+        sentry::with_scope(
+            |scope| {
+                configure_oauth_scope(
+                    scope,
+                    "oauth.callback.record_audit_event",
+                    Some(discord_user_fingerprint.as_str()),
+                )
+            },
+            || sentry::capture_error(&error),
+        );
+        error!("Failed to record link audit event");
+    }
+
+    callback_success(&page, MessageKey::LinkSucceeded)
 }
 
-fn callback_success(message: &str) -> Custom<RawHtml<String>> {
-    Custom(Status::Ok, RawHtml(render_page(true, message)))
+fn callback_success(page: &PageContext, message: MessageKey) -> Custom<RawHtml<String>> {
+    Custom(
+        Status::Ok,
+        RawHtml(page.pages.render_result(
+            true,
+            message.text(page.locale),
+            &page.nonce.0,
+            None,
+            page.locale,
+        )),
+    )
 }
 
-fn callback_error(message: &str, status: Status) -> Custom<RawHtml<String>> {
-    Custom(status, RawHtml(render_page(false, message)))
+fn callback_error(
+    page: &PageContext,
+    message: MessageKey,
+    status: Status,
+) -> Custom<RawHtml<String>> {
+    Custom(
+        status,
+        RawHtml(page.pages.render_result(
+            false,
+            message.text(page.locale),
+            &page.nonce.0,
+            Some(&page.request_id.0),
+            page.locale,
+        )),
+    )
 }
 
-fn callback_error_for_state_token(error: StateTokenError) -> Custom<RawHtml<String>> {
+fn callback_error_for_state_token(
+    page: &PageContext,
+    error: StateTokenError,
+) -> Custom<RawHtml<String>> {
     match error {
-        StateTokenError::Missing => callback_error(
-            "State parameter is missing from the callback.",
-            Status::BadRequest,
-        ),
-        StateTokenError::Invalid => callback_error(
-            "State parameter is invalid. Please restart the AniList login flow.",
-            Status::BadRequest,
-        ),
-        StateTokenError::Expired => callback_error(
-            "State parameter has expired. Please restart the AniList login flow.",
-            Status::BadRequest,
-        ),
-        StateTokenError::Replayed => callback_error(
-            "This login link has already been used. Please restart the AniList login flow.",
-            Status::BadRequest,
-        ),
-        StateTokenError::Internal => callback_error(
-            "Failed to validate the AniList login state. Please retry.",
-            Status::InternalServerError,
-        ),
+        StateTokenError::Missing => {
+            callback_error(page, MessageKey::StateMissing, Status::BadRequest)
+        }
+        StateTokenError::Invalid => {
+            callback_error(page, MessageKey::StateInvalid, Status::BadRequest)
+        }
+        StateTokenError::Expired => {
+            callback_error(page, MessageKey::StateExpired, Status::BadRequest)
+        }
+        StateTokenError::Replayed => {
+            callback_error(page, MessageKey::StateReplayed, Status::BadRequest)
+        }
+        StateTokenError::BrowserMismatch => {
+            callback_error(page, MessageKey::StateBrowserMismatch, Status::Forbidden)
+        }
+        StateTokenError::Internal => {
+            callback_error(page, MessageKey::StateInternal, Status::InternalServerError)
+        }
     }
 }
 
-fn render_page(success: bool, message: &str) -> String {
-    let (title, heading, hint, accent, icon_bg, icon_svg) = if success {
-        (
-            "Connected - Annie Mei",
-            "Account Connected",
-            "You can close this tab now.",
-            "#22c55e",
-            "rgba(34, 197, 94, 0.12)",
-            r##"<svg width="48" height="48" viewBox="0 0 24 24" fill="none" stroke="#22c55e" stroke-width="2" stroke-linecap="round" stroke-linejoin="round"><circle cx="12" cy="12" r="10"/><path d="M8 12l3 3 5-6"/></svg>"##,
-        )
-    } else {
-        (
-            "Error - Annie Mei",
-            "Something Went Wrong",
-            "Please try again from Discord.",
-            "#ef4444",
-            "rgba(239, 68, 68, 0.12)",
-            r##"<svg width="48" height="48" viewBox="0 0 24 24" fill="none" stroke="#ef4444" stroke-width="2" stroke-linecap="round" stroke-linejoin="round"><circle cx="12" cy="12" r="10"/><path d="M15 9l-6 6"/><path d="M9 9l6 6"/></svg>"##,
-        )
-    };
-
-    let escaped_message = message
-        .replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        .replace('"', "&quot;");
-
-    format!(
-        r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-<meta charset="utf-8">
-<meta name="viewport" content="width=device-width, initial-scale=1">
-<title>{title}</title>
-<link rel="icon" type="image/png" href="/static/favicon.png">
-<style>
-  *,*::before,*::after{{box-sizing:border-box;margin:0;padding:0}}
-  body{{
-    min-height:100vh;
-    display:flex;align-items:center;justify-content:center;
-    background:linear-gradient(145deg,#0f0f13 0%,#1a1a2e 100%);
-    font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',Roboto,'Helvetica Neue',Arial,sans-serif;
-    color:#e4e4e7;
-    padding:1rem;
-  }}
-  .card{{
-    width:100%;max-width:420px;
-    background:rgba(255,255,255,0.04);
-    border:1px solid rgba(255,255,255,0.08);
-    border-radius:16px;
-    backdrop-filter:blur(12px);-webkit-backdrop-filter:blur(12px);
-    box-shadow:0 8px 32px rgba(0,0,0,0.3);
-    padding:2.5rem 2rem;
-    text-align:center;
-    animation:fadeSlideIn .5s ease-out;
-  }}
-  .icon{{
-    width:72px;height:72px;
-    border-radius:50%;
-    background:{icon_bg};
-    display:flex;align-items:center;justify-content:center;
-    margin:0 auto 1.5rem;
-    animation:scaleIn .4s ease-out .15s both;
-  }}
-  h1{{
-    font-size:1.375rem;font-weight:600;
-    color:{accent};
-    margin-bottom:.75rem;
-  }}
-  .message{{
-    font-size:.9375rem;line-height:1.6;
-    color:#a1a1aa;
-    margin-bottom:1.5rem;
-  }}
-  .hint{{
-    font-size:.8125rem;
-    color:#52525b;
-  }}
-  .brand{{
-    margin-top:2rem;
-    font-size:.75rem;
-    color:#3f3f46;
-    letter-spacing:.04em;
-  }}
-  @keyframes fadeSlideIn{{
-    from{{opacity:0;transform:translateY(12px)}}
-    to{{opacity:1;transform:translateY(0)}}
-  }}
-  @keyframes scaleIn{{
-    from{{opacity:0;transform:scale(.6)}}
-    to{{opacity:1;transform:scale(1)}}
-  }}
-</style>
-</head>
-<body>
-  <div class="card">
-    <div class="icon">{icon_svg}</div>
-    <h1>{heading}</h1>
-    <p class="message">{escaped_message}</p>
-    <p class="hint">{hint}</p>
-    <p class="brand">Annie Mei</p>
-  </div>
-</body>
-</html>"#
-    )
-}
-
 #[cfg(test)]
 mod tests {
     use super::authorized;
     use crate::{
         routes::start::start,
         utils::{
-            functions::{fetch_credential_by_discord_user, upsert_oauth_credentials},
+            anilist_client::AniListClient,
+            database::Database,
+            fairings::{REQUEST_ID_HEADER, RequestIdHeader, SecurityHeaders},
+            functions::{
+                fetch_audit_events, fetch_credential_by_discord_user, upsert_oauth_credentials,
+            },
+            lifecycle::Lifecycle,
+            pages::Pages,
+            rate_limit::RateLimits,
+            reload::{ReloadableSettings, SettingsHandle},
+            store::Storage,
             structs::MyState,
         },
     };
     use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
     use chrono::Utc;
     use hmac::{Hmac, KeyInit, Mac};
-    use rocket::{Config, http::Status, local::asynchronous::Client, routes};
+    use rocket::tokio::{self, time::sleep};
+    use rocket::{
+        Config,
+        http::{Header, Status},
+        local::asynchronous::Client,
+        routes,
+    };
     use serde_json::json;
     use sha2::Sha256;
     use sqlx::{Pool, Postgres};
+    use std::time::Duration;
     use wiremock::{
         Mock, MockServer, ResponseTemplate,
         matchers::{method, path},
@@ -306,6 +282,10 @@ mod tests {
     const TEST_USERID_HASH_SALT: &str = "test-userid-hash-salt";
 
     fn signed_start_url(discord_user_id: &str) -> String {
+        signed_start_url_with_locale(discord_user_id, None)
+    }
+
+    fn signed_start_url_with_locale(discord_user_id: &str, locale: Option<&str>) -> String {
         type HmacSha256 = Hmac<Sha256>;
         let now = Utc::now().timestamp();
         let payload = json!({
@@ -316,6 +296,7 @@ mod tests {
             "nonce": "bM0XvTa5yT4K0z2yPxtA3A",
             "iat": now,
             "exp": now + 300,
+            "locale": locale,
         });
         let payload_segment =
             URL_SAFE_NO_PAD.encode(serde_json::to_vec(&payload).expect("payload should serialize"));
@@ -330,34 +311,59 @@ mod tests {
         pool: Pool<Postgres>,
         token_endpoint: String,
         user_endpoint: String,
+    ) -> rocket::Rocket<rocket::Build> {
+        build_test_rocket_with_storage(
+            Storage::postgres(pool.clone()),
+            Some(Database::new(pool)),
+            token_endpoint,
+            user_endpoint,
+        )
+    }
+
+    fn build_test_rocket_with_storage(
+        storage: Storage,
+        database: Option<Database>,
+        token_endpoint: String,
+        user_endpoint: String,
     ) -> rocket::Rocket<rocket::Build> {
         let figment =
             Config::figment().merge(("secret_key", "0123456789abcdef0123456789abcdef0123456789A="));
 
         let state = MyState {
             client_id: "client-id".to_string(),
-            client_secret: "client-secret".to_string(),
             redirect_uri: "http://127.0.0.1:8000/oauth/anilist/callback".to_string(),
-            context_signing_secret: TEST_CONTEXT_SECRET.to_string(),
             user_id_hash_salt: "test-userid-hash-salt".to_string(),
-            context_ttl_seconds: 300,
-            state_ttl_seconds: 600,
             token_endpoint,
             user_endpoint,
-            client: reqwest::Client::new(),
-            pool,
+            client: AniListClient::default(),
+            storage,
+            database,
+            settings: SettingsHandle::new(ReloadableSettings {
+                client_secret: "client-secret".to_string(),
+                context_signing_secret: TEST_CONTEXT_SECRET.to_string(),
+                context_ttl_seconds: 300,
+                state_ttl_seconds: 600,
+                admin_tokens: Vec::new(),
+            }),
+            rate_limits: RateLimits::per_minute(1_000, 1_000),
+            pages: Pages::embedded().clone(),
+            consent_page_enabled: false,
+            lifecycle: Lifecycle::default(),
         };
 
         rocket::custom(figment)
             .mount("/", routes![start, authorized])
+            .attach(RequestIdHeader)
+            .attach(SecurityHeaders)
             .manage(state)
     }
 
     async fn start_and_extract_state(client: &Client) -> String {
-        let response = client
-            .get(signed_start_url("555666777888"))
-            .dispatch()
-            .await;
+        start_and_extract_state_from(client, signed_start_url("555666777888")).await
+    }
+
+    async fn start_and_extract_state_from(client: &Client, start_url: String) -> String {
+        let response = client.get(start_url).dispatch().await;
         let location = response
             .headers()
             .get_one("location")
@@ -370,6 +376,56 @@ mod tests {
             .expect("redirect URL should contain state")
     }
 
+    #[rocket::async_test]
+    async fn authorized_happy_path_with_memory_store() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "access_1",
+                "expires_in": 3600,
+                "token_type": "Bearer"
+            })))
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": { "Viewer": { "id": 12345 } }
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let storage = Storage::memory();
+        let client = Client::tracked(build_test_rocket_with_storage(
+            storage.clone(),
+            None,
+            format!("{}/token", mock_server.uri()),
+            format!("{}/graphql", mock_server.uri()),
+        ))
+        .await
+        .expect("rocket client should build");
+
+        let state = start_and_extract_state(&client).await;
+        let callback = format!("/oauth/anilist/callback?state={state}&code=auth_code_1");
+        let response = client.get(callback.as_str()).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+
+        let persisted = storage
+            .credentials
+            .fetch_credential_by_discord_user("555666777888", TEST_USERID_HASH_SALT)
+            .await
+            .expect("fetch should not error")
+            .expect("credential should be persisted");
+        assert_eq!(persisted.anilist_id, 12345);
+        assert_eq!(persisted.access_token, "access_1");
+
+        let replay = client.get(callback.as_str()).dispatch().await;
+        assert_eq!(replay.status(), Status::BadRequest);
+    }
+
     #[sqlx::test(migrations = "./migrations")]
     async fn authorized_happy_path_persists_oauth_credentials(pool: Pool<Postgres>) {
         let mock_server = MockServer::start().await;
@@ -429,6 +485,93 @@ mod tests {
         assert_eq!(persisted.refresh_token.as_deref(), Some("refresh_1"));
         assert!(persisted.token_expires_at.is_some());
 
+        let events = fetch_audit_events("555666777888", 10, TEST_USERID_HASH_SALT, &pool)
+            .await
+            .expect("audit events should load");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, "linked");
+        assert_eq!(events[0].anilist_id, Some(12345));
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn shutdown_drain_waits_for_in_flight_callback(pool: Pool<Postgres>) {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({
+                        "access_token": "access_1",
+                        "refresh_token": "refresh_1",
+                        "expires_in": 3600,
+                        "token_type": "Bearer"
+                    }))
+                    .set_delay(Duration::from_millis(300)),
+            )
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": { "Viewer": { "id": 12345 } }
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let client = Client::tracked(build_test_rocket(
+            pool.clone(),
+            format!("{}/token", mock_server.uri()),
+            format!("{}/graphql", mock_server.uri()),
+        ))
+        .await
+        .expect("rocket client should build");
+        let lifecycle = client
+            .rocket()
+            .state::<MyState>()
+            .expect("state should be managed")
+            .lifecycle
+            .clone();
+
+        let state = start_and_extract_state(&client).await;
+        let callback = client
+            .get(format!(
+                "/oauth/anilist/callback?state={state}&code=auth_code_1"
+            ))
+            .dispatch();
+        let drain = async {
+            sleep(Duration::from_millis(100)).await;
+            lifecycle.drain(Duration::from_secs(5)).await
+        };
+        let (response, drained) = tokio::join!(callback, drain);
+
+        assert!(drained);
+        assert_eq!(response.status(), Status::Ok);
+        assert!(
+            response
+                .into_string()
+                .await
+                .expect("response should contain HTML")
+                .contains("Account Connected")
+        );
+        assert!(
+            fetch_credential_by_discord_user("555666777888", TEST_USERID_HASH_SALT, &pool)
+                .await
+                .expect("fetch should not error")
+                .is_some()
+        );
+
+        let status = client
+            .get(signed_start_url("555666777888"))
+            .dispatch()
+            .await
+            .status();
+        assert_eq!(status, Status::ServiceUnavailable);
+
         drop(client);
         pool.close().await;
     }
@@ -731,6 +874,197 @@ mod tests {
         pool.close().await;
     }
 
+    #[sqlx::test(migrations = "./migrations")]
+    async fn authorized_error_page_follows_accept_language(pool: Pool<Postgres>) {
+        let client = Client::tracked(build_test_rocket(
+            pool.clone(),
+            "https://anilist.co/api/v2/oauth/token".to_string(),
+            "https://graphql.anilist.co".to_string(),
+        ))
+        .await
+        .expect("rocket client should build");
+
+        let response = client
+            .get("/oauth/anilist/callback?state=invalid_state&code=auth_code_1")
+            .header(Header::new("Accept-Language", "ja-JP,ja;q=0.9,en;q=0.8"))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::BadRequest);
+        let body = response
+            .into_string()
+            .await
+            .expect("response should contain HTML");
+        assert!(body.contains(r#"<html lang="ja">"#));
+        assert!(body.contains("state パラメーターが無効です"));
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn authorized_prefers_discord_locale_over_accept_language(pool: Pool<Postgres>) {
+        let client = Client::tracked(build_test_rocket(
+            pool.clone(),
+            "https://anilist.co/api/v2/oauth/token".to_string(),
+            "https://graphql.anilist.co".to_string(),
+        ))
+        .await
+        .expect("rocket client should build");
+
+        let state = start_and_extract_state_from(
+            &client,
+            signed_start_url_with_locale("555666777888", Some("pt-BR")),
+        )
+        .await;
+
+        let response = client
+            .get(format!(
+                "/oauth/anilist/callback?state={state}&error=access_denied"
+            ))
+            .header(Header::new("Accept-Language", "es"))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::BadRequest);
+        let body = response
+            .into_string()
+            .await
+            .expect("response should contain HTML");
+        assert!(body.contains(r#"<html lang="pt">"#));
+        assert!(body.contains("A autorização foi negada no AniList. Tente novamente."));
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn authorized_rejects_callback_from_another_browser(pool: Pool<Postgres>) {
+        let client = Client::untracked(build_test_rocket(
+            pool.clone(),
+            "https://anilist.co/api/v2/oauth/token".to_string(),
+            "https://graphql.anilist.co".to_string(),
+        ))
+        .await
+        .expect("rocket client should build");
+
+        let response = client
+            .get(signed_start_url("555666777888"))
+            .dispatch()
+            .await;
+        let binding = response
+            .cookies()
+            .get("annie_mei_oauth_binding")
+            .cloned()
+            .expect("start should set the browser binding cookie");
+        let state = url::Url::parse(
+            response
+                .headers()
+                .get_one("location")
+                .expect("start should redirect"),
+        )
+        .expect("redirect URL should parse")
+        .query_pairs()
+        .find(|(key, _)| key == "state")
+        .map(|(_, value)| value.to_string())
+        .expect("redirect URL should contain state");
+        drop(response);
+
+        let callback = format!("/oauth/anilist/callback?state={state}&error=access_denied");
+        let other_browser = client.get(callback.clone()).dispatch().await;
+        assert_eq!(other_browser.status(), Status::Forbidden);
+        let body = other_browser
+            .into_string()
+            .await
+            .expect("response should contain HTML");
+        assert!(body.contains("This login was started in a different browser."));
+
+        let original_browser = client.get(callback).cookie(binding).dispatch().await;
+        assert_eq!(original_browser.status(), Status::BadRequest);
+        let body = original_browser
+            .into_string()
+            .await
+            .expect("response should contain HTML");
+        assert!(body.contains("Authorization was denied on AniList. Please try again."));
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn authorized_sets_security_headers(pool: Pool<Postgres>) {
+        let client = Client::tracked(build_test_rocket(
+            pool.clone(),
+            "https://anilist.co/api/v2/oauth/token".to_string(),
+            "https://graphql.anilist.co".to_string(),
+        ))
+        .await
+        .expect("rocket client should build");
+
+        let response = client
+            .get("/oauth/anilist/callback?state=invalid_state&code=auth_code_1")
+            .dispatch()
+            .await;
+
+        let csp = response
+            .headers()
+            .get_one("Content-Security-Policy")
+            .expect("callback should set a CSP")
+            .to_string();
+        assert!(csp.contains("default-src 'none'"));
+        assert!(csp.contains("frame-ancestors 'none'"));
+        assert_eq!(
+            response.headers().get_one("Referrer-Policy"),
+            Some("no-referrer")
+        );
+        assert_eq!(response.headers().get_one("X-Frame-Options"), Some("DENY"));
+
+        let nonce = csp
+            .split("'nonce-")
+            .nth(1)
+            .and_then(|rest| rest.split('\'').next())
+            .expect("CSP should carry a style nonce")
+            .to_string();
+        let body = response
+            .into_string()
+            .await
+            .expect("response should contain HTML");
+        assert!(body.contains(&format!("<style nonce=\"{nonce}\">")));
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn authorized_error_page_shows_request_id(pool: Pool<Postgres>) {
+        let client = Client::tracked(build_test_rocket(
+            pool.clone(),
+            "https://anilist.co/api/v2/oauth/token".to_string(),
+            "https://graphql.anilist.co".to_string(),
+        ))
+        .await
+        .expect("rocket client should build");
+
+        let response = client
+            .get("/oauth/anilist/callback?state=invalid_state&code=auth_code_1")
+            .header(Header::new(REQUEST_ID_HEADER, "edge-req-0042"))
+            .dispatch()
+            .await;
+
+        assert_eq!(
+            response.headers().get_one(REQUEST_ID_HEADER),
+            Some("edge-req-0042")
+        );
+        let body = response
+            .into_string()
+            .await
+            .expect("response should contain HTML");
+        assert!(body.contains("Reference: <code>edge-req-0042</code>"));
+
+        drop(client);
+        pool.close().await;
+    }
+
     #[sqlx::test(migrations = "./migrations")]
     async fn authorized_replayed_state_returns_error_page(pool: Pool<Postgres>) {
         let client = Client::tracked(build_test_rocket(
@@ -1,76 +1,370 @@
-use rocket::{Request, catch, response::content::RawHtml};
-
-const NOT_FOUND_HTML: &str = r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-<meta charset="utf-8">
-<meta name="viewport" content="width=device-width, initial-scale=1">
-<title>404 - Annie Mei</title>
-<link rel="icon" type="image/png" href="/static/favicon.png">
-<style>
-  *,*::before,*::after{box-sizing:border-box;margin:0;padding:0}
-  body{
-    min-height:100vh;
-    display:flex;align-items:center;justify-content:center;
-    background:linear-gradient(145deg,#0f0f13 0%,#1a1a2e 100%);
-    font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',Roboto,'Helvetica Neue',Arial,sans-serif;
-    color:#e4e4e7;
-    padding:1rem;
-  }
-  .card{
-    width:100%;max-width:420px;
-    background:rgba(255,255,255,0.04);
-    border:1px solid rgba(255,255,255,0.08);
-    border-radius:16px;
-    backdrop-filter:blur(12px);-webkit-backdrop-filter:blur(12px);
-    box-shadow:0 8px 32px rgba(0,0,0,0.3);
-    padding:2.5rem 2rem;
-    text-align:center;
-    animation:fadeSlideIn .5s ease-out;
-  }
-  .status{
-    font-size:4rem;font-weight:700;
-    color:#a78bfa;
-    margin-bottom:.5rem;
-    animation:scaleIn .4s ease-out .15s both;
-  }
-  h1{
-    font-size:1.375rem;font-weight:600;
-    color:#a78bfa;
-    margin-bottom:.75rem;
-  }
-  .message{
-    font-size:.9375rem;line-height:1.6;
-    color:#a1a1aa;
-    margin-bottom:1.5rem;
-  }
-  .brand{
-    margin-top:2rem;
-    font-size:.75rem;
-    color:#3f3f46;
-    letter-spacing:.04em;
-  }
-  @keyframes fadeSlideIn{
-    from{opacity:0;transform:translateY(12px)}
-    to{opacity:1;transform:translateY(0)}
-  }
-  @keyframes scaleIn{
-    from{opacity:0;transform:scale(.6)}
-    to{opacity:1;transform:scale(1)}
-  }
-</style>
-</head>
-<body>
-  <div class="card">
-    <p class="status">404</p>
-    <h1>Page Not Found</h1>
-    <p class="message">There's nothing here. If you're trying to link your AniList account, start from the bot command in Discord.</p>
-    <p class="brand">Annie Mei</p>
-  </div>
-</body>
-</html>"#;
+use crate::utils::{
+    i18n::MessageKey,
+    structs::{AdminErrorBody, ErrorBody, PageContext},
+};
+
+use rocket::{
+    Request, catch,
+    http::{Header, Status},
+    response::{content::RawHtml, status::Custom},
+    serde::json::Json,
+};
+
+/// Clients are told to wait a full bucket refill window before retrying.
+const RETRY_AFTER_SECONDS: &str = "60";
+
+/// Branded error card, or [`ErrorBody`] JSON when the client asks for it.
+#[derive(Responder)]
+pub enum ErrorResponse {
+    Html(Custom<RawHtml<String>>),
+    Json(Custom<Json<ErrorBody>>),
+}
+
+fn prefers_json(req: &Request) -> bool {
+    req.accept()
+        .is_some_and(|accept| accept.preferred().media_type().is_json())
+}
+
+fn error_response(
+    req: &Request,
+    status: Status,
+    error: &'static str,
+    message: MessageKey,
+) -> ErrorResponse {
+    let page = PageContext::for_request(req);
+    let message = message.text(page.locale);
+
+    if prefers_json(req) {
+        return ErrorResponse::Json(Custom(
+            status,
+            Json(ErrorBody {
+                error,
+                message,
+                request_id: page.request_id.0,
+            }),
+        ));
+    }
+
+    ErrorResponse::Html(Custom(
+        status,
+        RawHtml(page.pages.render_result(
+            false,
+            message,
+            &page.nonce.0,
+            Some(&page.request_id.0),
+            page.locale,
+        )),
+    ))
+}
+
+#[catch(404)]
+pub fn not_found(req: &Request) -> ErrorResponse {
+    if prefers_json(req) {
+        return error_response(
+            req,
+            Status::NotFound,
+            "not_found",
+            MessageKey::NotFoundMessage,
+        );
+    }
+
+    let page = PageContext::for_request(req);
+    ErrorResponse::Html(Custom(
+        Status::NotFound,
+        RawHtml(
+            page.pages
+                .render_not_found(&page.nonce.0, Some(&page.request_id.0), page.locale),
+        ),
+    ))
+}
+
+#[catch(400)]
+pub fn bad_request(req: &Request) -> ErrorResponse {
+    error_response(
+        req,
+        Status::BadRequest,
+        "bad_request",
+        MessageKey::ErrorBadRequest,
+    )
+}
+
+#[catch(403)]
+pub fn forbidden(req: &Request) -> ErrorResponse {
+    error_response(
+        req,
+        Status::Forbidden,
+        "forbidden",
+        MessageKey::ErrorForbidden,
+    )
+}
+
+#[catch(422)]
+pub fn unprocessable_entity(req: &Request) -> ErrorResponse {
+    error_response(
+        req,
+        Status::UnprocessableEntity,
+        "unprocessable_entity",
+        MessageKey::ErrorUnprocessable,
+    )
+}
+
+#[derive(Responder)]
+pub struct TooManyRequests {
+    inner: ErrorResponse,
+    retry_after: Header<'static>,
+}
+
+#[catch(429)]
+pub fn too_many_requests(req: &Request) -> TooManyRequests {
+    TooManyRequests {
+        inner: error_response(
+            req,
+            Status::TooManyRequests,
+            "too_many_requests",
+            MessageKey::ErrorTooManyRequests,
+        ),
+        retry_after: Header::new("Retry-After", RETRY_AFTER_SECONDS),
+    }
+}
+
+#[catch(500)]
+pub fn internal_error(req: &Request) -> ErrorResponse {
+    error_response(
+        req,
+        Status::InternalServerError,
+        "internal_error",
+        MessageKey::ErrorInternal,
+    )
+}
+
+#[catch(503)]
+pub fn service_unavailable(req: &Request) -> ErrorResponse {
+    error_response(
+        req,
+        Status::ServiceUnavailable,
+        "service_unavailable",
+        MessageKey::ErrorUnavailable,
+    )
+}
+
+#[catch(default)]
+pub fn default_error(status: Status, req: &Request) -> ErrorResponse {
+    error_response(req, status, "error", MessageKey::ErrorGeneric)
+}
+
+#[derive(Responder)]
+#[response(status = 401, content_type = "json")]
+pub struct AdminUnauthorized {
+    body: Json<AdminErrorBody>,
+    challenge: Header<'static>,
+}
+
+#[catch(401)]
+pub fn admin_unauthorized(_req: &Request) -> AdminUnauthorized {
+    AdminUnauthorized {
+        body: Json(AdminErrorBody {
+            error: "unauthorized",
+        }),
+        challenge: Header::new("WWW-Authenticate", "Bearer"),
+    }
+}
+
+#[catch(403)]
+pub fn admin_forbidden(_req: &Request) -> Json<AdminErrorBody> {
+    Json(AdminErrorBody { error: "forbidden" })
+}
 
 #[catch(404)]
-pub fn not_found(_req: &Request) -> RawHtml<&'static str> {
-    RawHtml(NOT_FOUND_HTML)
+pub fn admin_not_found(_req: &Request) -> Json<AdminErrorBody> {
+    Json(AdminErrorBody { error: "not_found" })
+}
+
+#[catch(400)]
+pub fn admin_bad_request(_req: &Request) -> Json<AdminErrorBody> {
+    Json(AdminErrorBody {
+        error: "bad_request",
+    })
+}
+
+#[catch(422)]
+pub fn admin_unprocessable(_req: &Request) -> Json<AdminErrorBody> {
+    Json(AdminErrorBody {
+        error: "invalid_request_body",
+    })
+}
+
+#[catch(500)]
+pub fn admin_internal_error(_req: &Request) -> Json<AdminErrorBody> {
+    Json(AdminErrorBody {
+        error: "internal_error",
+    })
+}
+
+#[catch(default)]
+pub fn admin_default_error(status: Status, _req: &Request) -> Custom<Json<AdminErrorBody>> {
+    Custom(status, Json(AdminErrorBody { error: "error" }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        admin_default_error, admin_internal_error, default_error, internal_error, not_found,
+    };
+    use crate::utils::fairings::SecurityHeaders;
+
+    use rocket::{
+        catchers,
+        http::{Accept, Status},
+        local::asynchronous::Client,
+        routes,
+    };
+
+    #[get("/panic")]
+    fn panics() -> &'static str {
+        panic!("handler failure")
+    }
+
+    #[get("/teapot")]
+    fn teapot() -> Status {
+        Status::ImATeapot
+    }
+
+    async fn error_client() -> Client {
+        let rocket = rocket::build()
+            .mount("/", routes![panics, teapot])
+            .register("/", catchers![not_found, internal_error, default_error])
+            .attach(SecurityHeaders);
+
+        Client::tracked(rocket)
+            .await
+            .expect("rocket client should build")
+    }
+
+    #[rocket::async_test]
+    async fn admin_errors_use_the_admin_body() {
+        let rocket = rocket::build()
+            .mount("/admin", routes![panics, teapot])
+            .register("/", catchers![internal_error, default_error])
+            .register(
+                "/admin",
+                catchers![admin_internal_error, admin_default_error],
+            );
+        let client = Client::tracked(rocket)
+            .await
+            .expect("rocket client should build");
+
+        for (path, status, body) in [
+            (
+                "/admin/panic",
+                Status::InternalServerError,
+                r#"{"error":"internal_error"}"#,
+            ),
+            ("/admin/teapot", Status::ImATeapot, r#"{"error":"error"}"#),
+        ] {
+            let response = client.get(path).dispatch().await;
+            assert_eq!(response.status(), status, "{path}");
+            assert_eq!(
+                response.into_string().await.as_deref(),
+                Some(body),
+                "{path}"
+            );
+        }
+    }
+
+    #[rocket::async_test]
+    async fn not_found_page_sets_security_headers() {
+        let client = error_client().await;
+        let response = client.get("/missing").dispatch().await;
+
+        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(
+            response.headers().get_one("Referrer-Policy"),
+            Some("no-referrer")
+        );
+        let csp = response
+            .headers()
+            .get_one("Content-Security-Policy")
+            .expect("404 page should set a CSP")
+            .to_string();
+        assert!(csp.contains("frame-ancestors 'none'"));
+
+        let body = response
+            .into_string()
+            .await
+            .expect("404 page should render");
+        assert!(!body.contains("{nonce}"));
+        let nonce = body
+            .split("<style nonce=\"")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .expect("404 page should carry a style nonce");
+        assert!(csp.contains(&format!("'nonce-{nonce}'")));
+    }
+
+    #[rocket::async_test]
+    async fn internal_error_renders_branded_page_with_reference() {
+        let client = error_client().await;
+
+        let response = client.get("/panic").dispatch().await;
+
+        assert_eq!(response.status(), Status::InternalServerError);
+        let body = response
+            .into_string()
+            .await
+            .expect("500 page should render");
+        assert!(body.contains("Something Went Wrong"));
+        assert!(body.contains("Reference: <code>"));
+    }
+
+    #[rocket::async_test]
+    async fn error_catchers_return_json_when_requested() {
+        let client = error_client().await;
+
+        let response = client.get("/panic").header(Accept::JSON).dispatch().await;
+
+        assert_eq!(response.status(), Status::InternalServerError);
+        let body: serde_json::Value = response
+            .into_json()
+            .await
+            .expect("500 response should be JSON");
+        assert_eq!(body["error"], "internal_error");
+        assert!(
+            body["request_id"]
+                .as_str()
+                .is_some_and(|request_id| !request_id.is_empty())
+        );
+    }
+
+    #[rocket::async_test]
+    async fn not_found_returns_json_when_requested() {
+        let client = error_client().await;
+
+        let response = client.get("/missing").header(Accept::JSON).dispatch().await;
+
+        assert_eq!(response.status(), Status::NotFound);
+        let body: serde_json::Value = response
+            .into_json()
+            .await
+            .expect("404 response should be JSON");
+        assert_eq!(body["error"], "not_found");
+        assert!(
+            body["message"]
+                .as_str()
+                .is_some_and(|message| !message.is_empty())
+        );
+    }
+
+    #[rocket::async_test]
+    async fn default_catcher_keeps_original_status() {
+        let client = error_client().await;
+
+        let response = client.get("/teapot").dispatch().await;
+
+        assert_eq!(response.status(), Status::ImATeapot);
+        let body = response
+            .into_string()
+            .await
+            .expect("error page should render");
+        assert!(body.contains("Reference: <code>"));
+    }
 }
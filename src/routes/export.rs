@@ -0,0 +1,359 @@
+use crate::utils::{
+    consts::CONTEXT_PURPOSE_EXPORT,
+    functions::{
+        AUDIT_ACTOR_USER, AUDIT_EVENT_EXPORTED, claim_context_nonce, export_user_data,
+        record_audit_event, verify_oauth_context,
+    },
+    i18n::{Locale, MessageKey},
+    observability::{configure_oauth_scope, identifier_fingerprint},
+    structs::{ClientRateLimit, MyState, RequestId, UserDataExport},
+};
+
+use chrono::{DateTime, Utc};
+use rocket::{
+    State,
+    http::{Header, Status},
+    response::status::BadRequest,
+    serde::json::Json,
+};
+
+#[derive(Responder)]
+pub enum ExportError {
+    BadRequest(BadRequest<String>),
+    /// Rendered by the matching catcher.
+    Status(Status),
+}
+
+#[derive(Responder)]
+#[response(content_type = "json")]
+pub struct ExportDownload {
+    body: Json<UserDataExport>,
+    disposition: Header<'static>,
+    cache_control: Header<'static>,
+}
+
+/// Self-service copy of everything stored about the Discord user in a signed
+/// `export` context.
+#[get("/oauth/anilist/export?<ctx>")]
+#[tracing::instrument(
+    name = "oauth.export",
+    skip(state, ctx, _rate_limit, request_id, locale),
+    fields(
+        request_id = %request_id.0,
+        discord_user_fingerprint = tracing::field::Empty,
+        context_valid = tracing::field::Empty
+    )
+)]
+pub async fn export(
+    ctx: &str,
+    _rate_limit: ClientRateLimit,
+    request_id: RequestId,
+    locale: Locale,
+    state: &State<MyState>,
+) -> Result<ExportDownload, ExportError> {
+    let span = tracing::Span::current();
+    let settings = state.settings.current();
+    let payload = verify_oauth_context(
+        ctx,
+        &settings.context_signing_secret,
+        settings.context_ttl_seconds,
+    )
+    .ok()
+    .filter(|payload| payload.purpose.as_deref() == Some(CONTEXT_PURPOSE_EXPORT))
+    .ok_or_else(|| {
+        span.record("context_valid", false);
+        info!("Data export rejected: invalid, expired or non-export context");
+        ExportError::BadRequest(BadRequest(
+            MessageKey::ContextInvalid.text(locale).to_string(),
+        ))
+    })?;
+    span.record("context_valid", true);
+
+    let discord_user_fingerprint =
+        identifier_fingerprint(&payload.discord_user_id, &state.user_id_hash_salt);
+    span.record("discord_user_fingerprint", &discord_user_fingerprint);
+
+    if !state
+        .rate_limits
+        .per_discord_user
+        .check(&discord_user_fingerprint)
+    {
+        info!("Data export rejected: per-Discord-user rate limit exceeded");
+        return Err(ExportError::Status(Status::TooManyRequests));
+    }
+
+    // Exports read the audit trail and session history straight from Postgres.
+    // Sessions held in Redis are short-lived and left out; see the README.
+    let Some(database) = &state.database else {
+        info!("Data export rejected: storage backend is not Postgres");
+        return Err(ExportError::Status(Status::ServiceUnavailable));
+    };
+
+    let database_error = |error: sqlx::Error, operation: &str| {
+        sentry::with_scope(
+            |scope| {
+                configure_oauth_scope(scope, operation, Some(discord_user_fingerprint.as_str()))
+            },
+            || sentry::capture_error(&error),
+        );
+        error!("Data export failed ({operation})");
+        ExportError::Status(Status::InternalServerError)
+    };
+
+    // Each export context downloads the data once; the bot issues a new one per request.
+    let expires_at = DateTime::from_timestamp(payload.exp, 0).unwrap_or_else(Utc::now);
+    let claimed = claim_context_nonce(&payload.nonce, expires_at, &database.pool)
+        .await
+        .map_err(|e| database_error(e, "oauth.export.claim"))?;
+    if !claimed {
+        info!("Data export rejected: context already used");
+        return Err(ExportError::BadRequest(BadRequest(
+            MessageKey::ContextInvalid.text(locale).to_string(),
+        )));
+    }
+
+    let export = export_user_data(
+        &payload.discord_user_id,
+        &state.user_id_hash_salt,
+        &database.pool,
+    )
+    .await
+    .map_err(|e| database_error(e, "oauth.export.collect"))?;
+
+    record_audit_event(
+        &payload.discord_user_id,
+        export
+            .credential
+            .as_ref()
+            .map(|credential| credential.anilist_id),
+        AUDIT_EVENT_EXPORTED,
+        AUDIT_ACTOR_USER,
+        None,
+        &state.user_id_hash_salt,
+        &database.pool,
+    )
+    .await
+    .map_err(|e| database_error(e, "oauth.export.audit"))?;
+
+    info!("Exported user data");
+    Ok(ExportDownload {
+        body: Json(export),
+        disposition: Header::new(
+            "Content-Disposition",
+            "attachment; filename=\"annie-mei-data-export.json\"",
+        ),
+        cache_control: Header::new("Cache-Control", "no-store"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::export;
+    use crate::utils::{
+        anilist_client::AniListClient,
+        database::Database,
+        functions::{
+            AUDIT_ACTOR_SYSTEM, AUDIT_EVENT_RELINK_REQUIRED, fetch_audit_events,
+            insert_oauth_session, mark_oauth_credentials_relink_required, record_audit_event,
+            upsert_oauth_credentials,
+        },
+        lifecycle::Lifecycle,
+        pages::Pages,
+        rate_limit::RateLimits,
+        reload::{ReloadableSettings, SettingsHandle},
+        store::Storage,
+        structs::MyState,
+    };
+
+    use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
+    use chrono::Utc;
+    use hmac::{Hmac, KeyInit, Mac};
+    use rocket::{Config, http::Status, local::asynchronous::Client, routes};
+    use serde_json::json;
+    use sha2::Sha256;
+    use sqlx::{Pool, Postgres};
+
+    const TEST_CONTEXT_SECRET: &str = "test-oauth-context-secret-for-unit-tests";
+    const TEST_USERID_HASH_SALT: &str = "test-userid-hash-salt";
+
+    fn signed_export_url(discord_user_id: &str, purpose: Option<&str>) -> String {
+        type HmacSha256 = Hmac<Sha256>;
+
+        let now = Utc::now().timestamp();
+        let payload = json!({
+            "v": 1,
+            "discord_user_id": discord_user_id,
+            "interaction_id": "12222333344445555",
+            "nonce": "bM0XvTa5yT4K0z2yPxtA3A",
+            "iat": now,
+            "exp": now + 300,
+            "purpose": purpose,
+        });
+        let payload_segment =
+            URL_SAFE_NO_PAD.encode(serde_json::to_vec(&payload).expect("payload should serialize"));
+        let mut mac = HmacSha256::new_from_slice(TEST_CONTEXT_SECRET.as_bytes()).expect("HMAC key");
+        mac.update(payload_segment.as_bytes());
+        let signature_segment = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
+
+        format!("/oauth/anilist/export?ctx={payload_segment}.{signature_segment}")
+    }
+
+    fn build_test_rocket(pool: Pool<Postgres>) -> rocket::Rocket<rocket::Build> {
+        let figment =
+            Config::figment().merge(("secret_key", "0123456789abcdef0123456789abcdef0123456789A="));
+
+        let state = MyState {
+            client_id: "client-id".to_string(),
+            redirect_uri: "http://127.0.0.1:8000/oauth/anilist/callback".to_string(),
+            user_id_hash_salt: TEST_USERID_HASH_SALT.to_string(),
+            token_endpoint: "https://anilist.co/api/v2/oauth/token".to_string(),
+            user_endpoint: "https://graphql.anilist.co".to_string(),
+            client: AniListClient::default(),
+            storage: Storage::postgres(pool.clone()),
+            database: Some(Database::new(pool)),
+            settings: SettingsHandle::new(ReloadableSettings {
+                client_secret: "client-secret".to_string(),
+                context_signing_secret: TEST_CONTEXT_SECRET.to_string(),
+                context_ttl_seconds: 300,
+                state_ttl_seconds: 600,
+                admin_tokens: Vec::new(),
+            }),
+            rate_limits: RateLimits::per_minute(1_000, 1_000),
+            pages: Pages::embedded().clone(),
+            consent_page_enabled: false,
+            lifecycle: Lifecycle::default(),
+        };
+
+        rocket::custom(figment)
+            .mount("/", routes![export])
+            .manage(state)
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn export_returns_everything_stored_with_tokens_masked(pool: Pool<Postgres>) {
+        upsert_oauth_credentials(
+            "555666777888",
+            4242,
+            "raw-access-token-never-exported",
+            Some("raw-refresh-token-never-exported"),
+            None,
+            TEST_USERID_HASH_SALT,
+            &pool,
+        )
+        .await
+        .expect("seed upsert should succeed");
+        mark_oauth_credentials_relink_required(
+            "555666777888",
+            "token_revoked",
+            TEST_USERID_HASH_SALT,
+            &pool,
+        )
+        .await
+        .expect("relink should succeed");
+        record_audit_event(
+            "555666777888",
+            Some(4242),
+            AUDIT_EVENT_RELINK_REQUIRED,
+            AUDIT_ACTOR_SYSTEM,
+            Some("token_revoked"),
+            TEST_USERID_HASH_SALT,
+            &pool,
+        )
+        .await
+        .expect("audit should succeed");
+        insert_oauth_session(
+            "secret-state",
+            "555666777888",
+            600,
+            None,
+            "secret-binding",
+            TEST_USERID_HASH_SALT,
+            &pool,
+        )
+        .await
+        .expect("session should insert");
+
+        let client = Client::tracked(build_test_rocket(pool.clone()))
+            .await
+            .expect("rocket client should build");
+        let response = client
+            .get(signed_export_url("555666777888", Some("export")))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(
+            response.headers().get_one("Content-Disposition"),
+            Some("attachment; filename=\"annie-mei-data-export.json\"")
+        );
+        assert_eq!(
+            response.headers().get_one("Cache-Control"),
+            Some("no-store")
+        );
+        let body = response
+            .into_string()
+            .await
+            .expect("export should have a body");
+        assert!(!body.contains("raw-access-token"));
+        assert!(!body.contains("secret-state"));
+        assert!(!body.contains("secret-binding"));
+
+        let export: serde_json::Value = serde_json::from_str(&body).expect("export should be JSON");
+        assert_eq!(export["discord_user_id"], "555666777888");
+        assert_eq!(export["credential"]["anilist_id"], 4242);
+        assert_eq!(export["credential"]["relink_reason"], "token_revoked");
+        assert_eq!(export["sessions"].as_array().map(Vec::len), Some(1));
+        assert_eq!(export["audit_events"].as_array().map(Vec::len), Some(1));
+        assert_eq!(export["relink_reasons"][0]["reason"], "token_revoked");
+
+        let events = fetch_audit_events("555666777888", 10, TEST_USERID_HASH_SALT, &pool)
+            .await
+            .expect("audit events should load");
+        assert_eq!(events[0].event, "exported");
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn export_context_can_only_be_used_once(pool: Pool<Postgres>) {
+        let client = Client::tracked(build_test_rocket(pool.clone()))
+            .await
+            .expect("rocket client should build");
+        let url = signed_export_url("555666777888", Some("export"));
+
+        let first = client.get(&url).dispatch().await;
+        assert_eq!(first.status(), Status::Ok);
+        drop(first);
+
+        let replay = client.get(&url).dispatch().await;
+        assert_eq!(replay.status(), Status::BadRequest);
+        drop(replay);
+
+        let events = fetch_audit_events("555666777888", 10, TEST_USERID_HASH_SALT, &pool)
+            .await
+            .expect("audit events should load");
+        assert_eq!(events.len(), 1, "a replay must not be audited as an export");
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn export_requires_export_purpose(pool: Pool<Postgres>) {
+        let client = Client::tracked(build_test_rocket(pool.clone()))
+            .await
+            .expect("rocket client should build");
+
+        for purpose in [None, Some("link")] {
+            let response = client
+                .get(signed_export_url("555666777888", purpose))
+                .dispatch()
+                .await;
+            assert_eq!(response.status(), Status::BadRequest);
+        }
+
+        drop(client);
+        pool.close().await;
+    }
+}
@@ -1,4 +1,9 @@
-use crate::utils::structs::MyState;
+use crate::utils::{
+    circuit_breaker::CircuitState,
+    functions::fetch_migration_status,
+    heartbeat::JobHeartbeats,
+    structs::{MigrationState, MyState},
+};
 use rocket::{
     State,
     http::Status,
@@ -6,132 +11,479 @@ use rocket::{
     serde::{Serialize, json::Json},
     tokio::time::{Duration, timeout},
 };
+use sqlx::PgPool;
+use std::collections::BTreeMap;
 
 const HEALTHZ_DATABASE_TIMEOUT: Duration = Duration::from_secs(2);
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(crate = "rocket::serde", rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    Degraded,
+    Failed,
+}
+
 #[derive(Serialize)]
 #[serde(crate = "rocket::serde")]
-pub struct HealthChecks<'a> {
-    database: &'a str,
+pub struct Check {
+    status: CheckStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+impl Check {
+    fn ok(detail: Option<String>) -> Self {
+        Self {
+            status: CheckStatus::Ok,
+            detail,
+        }
+    }
+
+    fn degraded(detail: String) -> Self {
+        Self {
+            status: CheckStatus::Degraded,
+            detail: Some(detail),
+        }
+    }
+
+    fn failed(detail: &str) -> Self {
+        Self {
+            status: CheckStatus::Failed,
+            detail: Some(detail.to_string()),
+        }
+    }
 }
 
+/// `database`, `migrations` and `redis` are critical and fail readiness. The others
+/// only degrade it, since restarting this instance would not fix them.
 #[derive(Serialize)]
 #[serde(crate = "rocket::serde")]
-pub struct HealthResponse<'a> {
-    status: &'a str,
-    checks: HealthChecks<'a>,
+pub struct HealthChecks {
+    database: Check,
+    migrations: Check,
+    /// Present only when OAuth sessions are kept in Redis.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    redis: Option<Check>,
+    pool: Check,
+    anilist: Check,
+    jobs: BTreeMap<&'static str, Check>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct HealthResponse {
+    status: &'static str,
+    checks: HealthChecks,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct LiveResponse {
+    status: &'static str,
+}
+
+/// The process is up and serving requests. Checks no dependencies.
+#[get("/livez")]
+pub fn livez() -> Json<LiveResponse> {
+    Json(LiveResponse { status: "alive" })
+}
+
+#[get("/readyz")]
+#[tracing::instrument(name = "readyz", skip_all)]
+pub async fn readyz(state: &State<MyState>) -> Custom<Json<HealthResponse>> {
+    readiness(state).await
 }
 
+/// Same as `/readyz`, kept for deployments that still probe it.
 #[get("/healthz")]
-#[tracing::instrument(name = "healthz", skip(state))]
-pub async fn healthz(state: &State<MyState>) -> Custom<Json<HealthResponse<'static>>> {
+#[tracing::instrument(name = "healthz", skip_all)]
+pub async fn healthz(state: &State<MyState>) -> Custom<Json<HealthResponse>> {
+    readiness(state).await
+}
+
+async fn readiness(state: &MyState) -> Custom<Json<HealthResponse>> {
+    let (database, migrations, pool) = match &state.database {
+        Some(db) => {
+            let database = database_check(&db.pool).await;
+            let migrations = if database.status == CheckStatus::Ok {
+                migrations_check(&db.pool).await
+            } else {
+                Check::failed("database unavailable")
+            };
+            (database, migrations, pool_check(&db.pool))
+        }
+        None => {
+            let unused = || Check::ok(Some("postgres not used".to_string()));
+            (unused(), unused(), unused())
+        }
+    };
+
+    let checks = HealthChecks {
+        database,
+        migrations,
+        redis: redis_check(state).await,
+        pool,
+        anilist: anilist_check(state).await,
+        jobs: jobs_check(state.lifecycle.heartbeats()),
+    };
+
+    let critical_failed = [&checks.database, &checks.migrations]
+        .into_iter()
+        .chain(checks.redis.as_ref())
+        .any(|check| check.status != CheckStatus::Ok);
+    let degraded = [&checks.pool, &checks.anilist]
+        .into_iter()
+        .chain(checks.jobs.values())
+        .any(|check| check.status != CheckStatus::Ok);
+
+    let (status, label) = if critical_failed {
+        (Status::ServiceUnavailable, "unhealthy")
+    } else if degraded {
+        (Status::Ok, "degraded")
+    } else {
+        (Status::Ok, "healthy")
+    };
+
+    Custom(
+        status,
+        Json(HealthResponse {
+            status: label,
+            checks,
+        }),
+    )
+}
+
+async fn database_check(pool: &PgPool) -> Check {
     let db_result = timeout(
         HEALTHZ_DATABASE_TIMEOUT,
-        sqlx::query_scalar::<_, i32>("SELECT 1").fetch_one(&state.pool),
+        sqlx::query_scalar::<_, i32>("SELECT 1").fetch_one(pool),
     )
     .await;
 
     match db_result {
-        Ok(Ok(_)) => Custom(
-            Status::Ok,
-            Json(HealthResponse {
-                status: "healthy",
-                checks: HealthChecks { database: "ok" },
-            }),
-        ),
+        Ok(Ok(_)) => Check::ok(None),
         Ok(Err(_)) => {
             error!("Health check failed for database dependency");
-
-            Custom(
-                Status::ServiceUnavailable,
-                Json(HealthResponse {
-                    status: "unhealthy",
-                    checks: HealthChecks { database: "error" },
-                }),
-            )
+            Check::failed("query failed")
         }
         Err(_) => {
             error!("Health check timed out waiting for database dependency");
+            Check::failed("timed out")
+        }
+    }
+}
+
+async fn migrations_check(pool: &PgPool) -> Check {
+    let Ok(Ok(statuses)) = timeout(HEALTHZ_DATABASE_TIMEOUT, fetch_migration_status(pool)).await
+    else {
+        error!("Health check could not read migration status");
+        return Check::failed("status unavailable");
+    };
+
+    let count = |state: MigrationState| {
+        statuses
+            .iter()
+            .filter(|migration| migration.state == state)
+            .count()
+    };
+    let (pending, failed, mismatched) = (
+        count(MigrationState::Pending),
+        count(MigrationState::Failed),
+        count(MigrationState::ChecksumMismatch),
+    );
+
+    if pending + failed + mismatched == 0 {
+        return Check::ok(None);
+    }
+
+    error!("Health check found unapplied migrations");
+    Check {
+        status: CheckStatus::Failed,
+        detail: Some(format!(
+            "{pending} pending, {failed} failed, {mismatched} checksum mismatches"
+        )),
+    }
+}
 
-            Custom(
-                Status::ServiceUnavailable,
-                Json(HealthResponse {
-                    status: "unhealthy",
-                    checks: HealthChecks { database: "error" },
-                }),
-            )
+async fn redis_check(state: &MyState) -> Option<Check> {
+    let check = match timeout(HEALTHZ_DATABASE_TIMEOUT, state.storage.sessions.ping()).await {
+        Ok(None) => return None,
+        Ok(Some(Ok(()))) => Check::ok(None),
+        Ok(Some(Err(_))) => {
+            error!("Health check failed for redis dependency");
+            Check::failed("ping failed")
+        }
+        Err(_) => {
+            error!("Health check timed out waiting for redis dependency");
+            Check::failed("timed out")
         }
+    };
+    Some(check)
+}
+
+fn pool_check(pool: &PgPool) -> Check {
+    let max = pool.options().get_max_connections();
+    let in_use = pool.size().saturating_sub(pool.num_idle() as u32);
+    let detail = format!("{in_use}/{max} connections in use");
+
+    if in_use >= max {
+        Check::degraded(detail)
+    } else {
+        Check::ok(Some(detail))
     }
 }
 
+async fn anilist_check(state: &MyState) -> Check {
+    let circuit = state.client.breaker().state();
+    if circuit == CircuitState::Open {
+        return Check::degraded("circuit breaker open".to_string());
+    }
+    if !state.client.is_reachable(&state.user_endpoint).await {
+        return Check::degraded("unreachable".to_string());
+    }
+
+    let detail =
+        (circuit == CircuitState::HalfOpen).then(|| "circuit breaker half-open".to_string());
+    Check::ok(detail)
+}
+
+fn jobs_check(heartbeats: &JobHeartbeats) -> BTreeMap<&'static str, Check> {
+    heartbeats
+        .snapshot()
+        .into_iter()
+        .map(|(job, age, stale)| {
+            let detail = age.map_or_else(
+                || "not run yet".to_string(),
+                |seconds| format!("last run {seconds}s ago"),
+            );
+            let check = if stale {
+                Check::degraded(detail)
+            } else {
+                Check::ok(Some(detail))
+            };
+            (job, check)
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::healthz;
-    use crate::utils::structs::MyState;
+    use super::{healthz, livez, readyz};
+    use crate::utils::{
+        anilist_client::{AniListClient, HttpClientSettings},
+        database::Database,
+        functions::SessionConsumeError,
+        i18n::Locale,
+        lifecycle::Lifecycle,
+        pages::Pages,
+        rate_limit::RateLimits,
+        reload::{ReloadableSettings, SettingsHandle},
+        store::{SessionStore, Storage},
+        structs::{MyState, OAuthSession},
+    };
     use rocket::{Config, http::Status, local::asynchronous::Client, routes};
     use sqlx::{Pool, Postgres};
+    use std::sync::Arc;
+    use wiremock::{Mock, MockServer, ResponseTemplate, matchers::method};
 
-    fn build_test_rocket(pool: Pool<Postgres>) -> rocket::Rocket<rocket::Build> {
+    fn build_test_rocket(
+        pool: Pool<Postgres>,
+        user_endpoint: &str,
+    ) -> rocket::Rocket<rocket::Build> {
+        build_test_rocket_with_storage(Storage::postgres(pool.clone()), pool, user_endpoint)
+    }
+
+    fn build_test_rocket_with_storage(
+        storage: Storage,
+        pool: Pool<Postgres>,
+        user_endpoint: &str,
+    ) -> rocket::Rocket<rocket::Build> {
         let figment =
             Config::figment().merge(("secret_key", "0123456789abcdef0123456789abcdef0123456789A="));
 
         let state = MyState {
             client_id: "client-id".to_string(),
-            client_secret: "client-secret".to_string(),
             redirect_uri: "http://127.0.0.1:8000/oauth/anilist/callback".to_string(),
-            context_signing_secret: "context-signing-secret".to_string(),
             user_id_hash_salt: "test-userid-hash-salt".to_string(),
-            context_ttl_seconds: 300,
-            state_ttl_seconds: 600,
             token_endpoint: "https://anilist.co/api/v2/oauth/token".to_string(),
-            user_endpoint: "https://graphql.anilist.co".to_string(),
-            client: reqwest::Client::new(),
-            pool,
+            user_endpoint: user_endpoint.to_string(),
+            client: AniListClient::default(),
+            storage,
+            database: Some(Database::new(pool)),
+            settings: SettingsHandle::new(ReloadableSettings {
+                client_secret: "client-secret".to_string(),
+                context_signing_secret: "context-signing-secret".to_string(),
+                context_ttl_seconds: 300,
+                state_ttl_seconds: 600,
+                admin_tokens: Vec::new(),
+            }),
+            rate_limits: RateLimits::per_minute(1_000, 1_000),
+            pages: Pages::embedded().clone(),
+            consent_page_enabled: false,
+            lifecycle: Lifecycle::default(),
         };
 
         rocket::custom(figment)
-            .mount("/", routes![healthz])
+            .mount("/", routes![healthz, livez, readyz])
             .manage(state)
     }
 
+    /// Stands in for a Redis session store whose server is down.
+    struct UnreachableSessions;
+
+    #[rocket::async_trait]
+    impl SessionStore for UnreachableSessions {
+        async fn insert_oauth_session(
+            &self,
+            _state: &str,
+            _discord_user_id: &str,
+            _ttl_seconds: i64,
+            _locale: Option<Locale>,
+            _browser_binding_hash: &str,
+            _user_id_hash_salt: &str,
+        ) -> Result<(), sqlx::Error> {
+            Err(sqlx::Error::PoolClosed)
+        }
+
+        async fn consume_oauth_session(
+            &self,
+            _state: &str,
+            _browser_binding_hash: Option<&str>,
+        ) -> Result<OAuthSession, SessionConsumeError> {
+            Err(SessionConsumeError::Db(sqlx::Error::PoolClosed))
+        }
+
+        async fn ping(&self) -> Option<Result<(), sqlx::Error>> {
+            Some(Err(sqlx::Error::PoolClosed))
+        }
+    }
+
+    async fn mock_anilist() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .respond_with(ResponseTemplate::new(405))
+            .mount(&server)
+            .await;
+        server
+    }
+
     #[sqlx::test(migrations = "./migrations")]
-    async fn healthz_returns_healthy_when_database_is_available(pool: Pool<Postgres>) {
-        let client = Client::tracked(build_test_rocket(pool.clone()))
+    async fn readyz_returns_healthy_when_dependencies_are_available(pool: Pool<Postgres>) {
+        let anilist = mock_anilist().await;
+        let client = Client::tracked(build_test_rocket(pool.clone(), &anilist.uri()))
             .await
             .expect("rocket client should build");
 
-        let response = client.get("/healthz").dispatch().await;
+        let response = client.get("/readyz").dispatch().await;
 
         assert_eq!(response.status(), Status::Ok);
-        let body = response
-            .into_string()
+        let body: serde_json::Value = response
+            .into_json()
             .await
-            .expect("health endpoint should return body");
-        assert!(body.contains("\"status\":\"healthy\""));
-        assert!(body.contains("\"database\":\"ok\""));
+            .expect("readiness endpoint should return JSON");
+        assert_eq!(body["status"], "healthy");
+        for check in ["database", "migrations", "pool", "anilist"] {
+            assert_eq!(body["checks"][check]["status"], "ok", "{check}");
+        }
+        assert!(body["checks"].get("redis").is_none());
+
+        let legacy = client.get("/healthz").dispatch().await;
+        assert_eq!(legacy.status(), Status::Ok);
 
+        drop(legacy);
         drop(client);
         pool.close().await;
     }
 
     #[sqlx::test(migrations = "./migrations")]
-    async fn healthz_returns_unhealthy_when_database_is_unavailable(pool: Pool<Postgres>) {
-        let client = Client::tracked(build_test_rocket(pool.clone()))
+    async fn readyz_returns_unhealthy_when_database_is_unavailable(pool: Pool<Postgres>) {
+        let anilist = mock_anilist().await;
+        let client = Client::tracked(build_test_rocket(pool.clone(), &anilist.uri()))
             .await
             .expect("rocket client should build");
 
         pool.close().await;
 
-        let response = client.get("/healthz").dispatch().await;
+        let response = client.get("/readyz").dispatch().await;
 
         assert_eq!(response.status(), Status::ServiceUnavailable);
-        let body = response
-            .into_string()
+        let body: serde_json::Value = response
+            .into_json()
             .await
-            .expect("health endpoint should return body");
-        assert!(body.contains("\"status\":\"unhealthy\""));
-        assert!(body.contains("\"database\":\"error\""));
+            .expect("readiness endpoint should return JSON");
+        assert_eq!(body["status"], "unhealthy");
+        assert_eq!(body["checks"]["database"]["status"], "failed");
+        assert_eq!(body["checks"]["migrations"]["status"], "failed");
+
+        let live = client.get("/livez").dispatch().await;
+        assert_eq!(live.status(), Status::Ok);
+        assert_eq!(
+            live.into_string().await.as_deref(),
+            Some(r#"{"status":"alive"}"#)
+        );
+
+        drop(client);
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn readyz_degrades_for_non_critical_checks(pool: Pool<Postgres>) {
+        let rocket = build_test_rocket(pool.clone(), "http://127.0.0.1:9");
+        let state = rocket.state::<MyState>().expect("state should be managed");
+        for _ in 0..HttpClientSettings::default().breaker_failure_threshold {
+            state.client.breaker().record_failure();
+        }
+        state
+            .lifecycle
+            .heartbeats()
+            .register("retention", std::time::Duration::from_secs(3600));
+        let client = Client::tracked(rocket)
+            .await
+            .expect("rocket client should build");
+
+        let response = client.get("/readyz").dispatch().await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let body: serde_json::Value = response
+            .into_json()
+            .await
+            .expect("readiness endpoint should return JSON");
+        assert_eq!(body["status"], "degraded");
+        assert_eq!(body["checks"]["anilist"]["status"], "degraded");
+        assert_eq!(body["checks"]["anilist"]["detail"], "circuit breaker open");
+        assert_eq!(body["checks"]["jobs"]["retention"]["detail"], "not run yet");
 
         drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn readyz_fails_when_the_redis_session_store_is_down(pool: Pool<Postgres>) {
+        let anilist = mock_anilist().await;
+        let storage = Storage {
+            sessions: Arc::new(UnreachableSessions),
+            ..Storage::postgres(pool.clone())
+        };
+        let client = Client::tracked(build_test_rocket_with_storage(
+            storage,
+            pool.clone(),
+            &anilist.uri(),
+        ))
+        .await
+        .expect("rocket client should build");
+
+        let response = client.get("/readyz").dispatch().await;
+
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+        let body: serde_json::Value = response
+            .into_json()
+            .await
+            .expect("readiness endpoint should return JSON");
+        assert_eq!(body["status"], "unhealthy");
+        assert_eq!(body["checks"]["redis"]["status"], "failed");
+        assert_eq!(body["checks"]["database"]["status"], "ok");
+
+        drop(client);
+        pool.close().await;
     }
 }
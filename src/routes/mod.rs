@@ -1,4 +1,6 @@
+pub mod admin;
 pub mod authorized;
 pub mod catchers;
+pub mod export;
 pub mod healthz;
 pub mod start;
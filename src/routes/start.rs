@@ -1,98 +1,302 @@
 use crate::utils::{
-    consts::ANILIST_AUTH,
-    functions::{get_state_token, insert_oauth_session, verify_oauth_context},
+    consts::{ANILIST_AUTH, BINDING_COOKIE_PATH, BROWSER_BINDING_COOKIE, CONTEXT_PURPOSE_EXPORT},
+    functions::{browser_binding_hash, discord_avatar_url, get_state_token, verify_oauth_context},
+    i18n::{Locale, MessageKey},
     observability::{configure_oauth_scope, identifier_fingerprint},
-    structs::MyState,
+    pages::ConsentDetails,
+    structs::{ClientRateLimit, MyState, OAuthContextPayload, PageContext, StartForm},
 };
 
-use rocket::{State, response::Redirect, response::status::BadRequest};
+use rocket::{
+    Config, State,
+    form::Form,
+    http::{Cookie, CookieJar, SameSite, Status},
+    response::{
+        Redirect,
+        content::RawHtml,
+        status::{BadRequest, Custom},
+    },
+    time::Duration,
+};
 use url::Url;
 
+#[derive(Responder)]
+pub enum StartError {
+    BadRequest(BadRequest<String>),
+    /// Handled by the `429` catcher so the user sees the styled page.
+    RateLimited(Status),
+    /// AniList's circuit breaker is open or the server is shutting down.
+    Unavailable(Custom<RawHtml<String>>),
+}
+
+impl From<BadRequest<String>> for StartError {
+    fn from(error: BadRequest<String>) -> Self {
+        StartError::BadRequest(error)
+    }
+}
+
+#[derive(Responder)]
+pub enum StartResponse {
+    Redirect(Box<Redirect>),
+    Consent(RawHtml<String>),
+}
+
 #[get("/oauth/anilist/start?<ctx>")]
 #[tracing::instrument(
     name = "oauth.start",
-    skip(state, ctx),
-    fields(discord_user_fingerprint = tracing::field::Empty, context_valid = tracing::field::Empty)
+    skip(state, ctx, _rate_limit, page, cookies, config),
+    fields(
+        request_id = %page.request_id.0,
+        discord_user_fingerprint = tracing::field::Empty,
+        context_valid = tracing::field::Empty
+    )
 )]
-pub async fn start(ctx: &str, state: &State<MyState>) -> Result<Redirect, BadRequest<String>> {
+pub async fn start(
+    ctx: &str,
+    _rate_limit: ClientRateLimit,
+    page: PageContext,
+    cookies: &CookieJar<'_>,
+    config: &Config,
+    state: &State<MyState>,
+) -> Result<StartResponse, StartError> {
+    let (payload, locale) = verify_start_context(ctx, page.locale, state)?;
+    ensure_accepting_logins(&page, locale, state)?;
+
+    if !state.consent_page_enabled {
+        return create_session(&payload, locale, cookies, config, state)
+            .await
+            .map(|redirect| StartResponse::Redirect(Box::new(redirect)));
+    }
+
+    let avatar_url = payload
+        .discord_avatar
+        .as_deref()
+        .and_then(|hash| discord_avatar_url(&payload.discord_user_id, hash));
+    let consent = ConsentDetails {
+        ctx,
+        discord_user_id: &payload.discord_user_id,
+        discord_username: payload.discord_username.as_deref(),
+        avatar_url: avatar_url.as_deref(),
+    };
+
+    info!("Showing OAuth consent page");
+    Ok(StartResponse::Consent(RawHtml(page.pages.render_consent(
+        &consent,
+        &page.nonce.0,
+        None,
+        locale,
+    ))))
+}
+
+/// The consent page's Continue button. Re-verifies the context before creating the session.
+#[post("/oauth/anilist/start", data = "<form>")]
+#[tracing::instrument(
+    name = "oauth.start.confirm",
+    skip(state, form, _rate_limit, page, cookies, config),
+    fields(
+        request_id = %page.request_id.0,
+        discord_user_fingerprint = tracing::field::Empty,
+        context_valid = tracing::field::Empty
+    )
+)]
+pub async fn confirm_start(
+    form: Form<StartForm<'_>>,
+    _rate_limit: ClientRateLimit,
+    page: PageContext,
+    cookies: &CookieJar<'_>,
+    config: &Config,
+    state: &State<MyState>,
+) -> Result<Redirect, StartError> {
+    let (payload, locale) = verify_start_context(form.ctx, page.locale, state)?;
+    ensure_accepting_logins(&page, locale, state)?;
+    create_session(&payload, locale, cookies, config, state).await
+}
+
+/// Verifies the signed context and picks the page locale. Discord's interaction locale
+/// wins over the browser's Accept-Language.
+fn verify_start_context(
+    ctx: &str,
+    locale: Locale,
+    state: &MyState,
+) -> Result<(OAuthContextPayload, Locale), StartError> {
     let span = tracing::Span::current();
+    let settings = state.settings.current();
     let payload = verify_oauth_context(
         ctx,
-        &state.context_signing_secret,
-        state.context_ttl_seconds,
+        &settings.context_signing_secret,
+        settings.context_ttl_seconds,
     )
-    .map_err(|_| {
+    .ok()
+    .filter(|payload| payload.purpose.as_deref() != Some(CONTEXT_PURPOSE_EXPORT))
+    .ok_or_else(|| {
         span.record("context_valid", false);
         info!("OAuth start rejected: invalid or expired context");
-        BadRequest("Invalid or expired OAuth context".to_string())
+        BadRequest(MessageKey::ContextInvalid.text(locale).to_string())
     })?;
     span.record("context_valid", true);
 
+    let locale = payload
+        .locale
+        .as_deref()
+        .and_then(Locale::from_tag)
+        .unwrap_or(locale);
+
+    Ok((payload, locale))
+}
+
+/// Keeps users from being sent to an AniList login that is likely to fail, either
+/// because AniList is down or because this instance is shutting down and the
+/// callback could land on it mid-restart.
+fn ensure_accepting_logins(
+    page: &PageContext,
+    locale: Locale,
+    state: &MyState,
+) -> Result<(), StartError> {
+    let message = if state.lifecycle.is_draining() {
+        info!("OAuth start rejected: shutting down");
+        MessageKey::ErrorUnavailable
+    } else if !state.client.breaker().allows_requests() {
+        info!("OAuth start rejected: AniList circuit breaker is open");
+        MessageKey::UpstreamDegraded
+    } else {
+        return Ok(());
+    };
+
+    Err(StartError::Unavailable(Custom(
+        Status::ServiceUnavailable,
+        RawHtml(page.pages.render_result(
+            false,
+            message.text(locale),
+            &page.nonce.0,
+            Some(&page.request_id.0),
+            locale,
+        )),
+    )))
+}
+
+/// Creates the session and binds it to this browser with a private cookie, so the
+/// callback is only accepted from the browser that started the flow.
+async fn create_session(
+    payload: &OAuthContextPayload,
+    locale: Locale,
+    cookies: &CookieJar<'_>,
+    config: &Config,
+    state: &MyState,
+) -> Result<Redirect, StartError> {
+    let state_ttl_seconds = state.settings.current().state_ttl_seconds;
     let discord_user_fingerprint =
         identifier_fingerprint(&payload.discord_user_id, &state.user_id_hash_salt);
-    span.record("discord_user_fingerprint", &discord_user_fingerprint);
+    tracing::Span::current().record("discord_user_fingerprint", &discord_user_fingerprint);
+
+    if !state
+        .rate_limits
+        .per_discord_user
+        .check(&discord_user_fingerprint)
+    {
+        info!("OAuth start rejected: per-Discord-user rate limit exceeded");
+        return Err(StartError::RateLimited(Status::TooManyRequests));
+    }
 
     let state_token = get_state_token();
+    let browser_binding = get_state_token();
     let params = [
         ("client_id", state.client_id.as_str()),
         ("redirect_uri", state.redirect_uri.as_str()),
         ("response_type", "code"),
         ("state", state_token.as_str()),
     ];
-    let url = Url::parse_with_params(ANILIST_AUTH, &params)
-        .map_err(|e| BadRequest(format!("Failed to build AniList auth URL: {e}")))?;
-
-    insert_oauth_session(
-        &state_token,
-        &payload.discord_user_id,
-        state.state_ttl_seconds,
-        state.user_id_hash_salt.as_str(),
-        &state.pool,
-    )
-    .await
-    .map_err(|e| {
-        sentry::with_scope(
-            |scope| {
-                configure_oauth_scope(
-                    scope,
-                    "oauth.start.create_session",
-                    Some(discord_user_fingerprint.as_str()),
-                )
-            },
-            || sentry::capture_error(&e),
-        );
-        error!("Failed to create OAuth session");
-        BadRequest("Failed to create OAuth session. Please try again.".to_string())
+    let url = Url::parse_with_params(ANILIST_AUTH, &params).map_err(|e| {
+        error!("Failed to build AniList auth URL: {e}");
+        BadRequest(MessageKey::StartFailed.text(locale).to_string())
     })?;
 
+    state
+        .storage
+        .sessions
+        .insert_oauth_session(
+            &state_token,
+            &payload.discord_user_id,
+            state_ttl_seconds,
+            payload.locale.as_deref().and_then(Locale::from_tag),
+            &browser_binding_hash(&browser_binding),
+            state.user_id_hash_salt.as_str(),
+        )
+        .await
+        .map_err(|e| {
+            sentry::with_scope(
+                |scope| {
+                    configure_oauth_scope(
+                        scope,
+                        "oauth.start.create_session",
+                        Some(discord_user_fingerprint.as_str()),
+                    )
+                },
+                || sentry::capture_error(&e),
+            );
+            error!("Failed to create OAuth session");
+            BadRequest(MessageKey::SessionCreateFailed.text(locale).to_string())
+        })?;
+
+    cookies.add_private(
+        Cookie::build((BROWSER_BINDING_COOKIE, browser_binding))
+            .path(BINDING_COOKIE_PATH)
+            .http_only(true)
+            // Lax so the cookie survives the top-level redirect back from AniList.
+            .same_site(SameSite::Lax)
+            .secure(config.profile == Config::RELEASE_PROFILE)
+            .max_age(Duration::seconds(state_ttl_seconds)),
+    );
+
     info!("Created OAuth session");
     Ok(Redirect::to(url.to_string()))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::start;
+    use super::{confirm_start, start};
+    use crate::routes::catchers::too_many_requests;
     use crate::utils::{
-        functions::verify_oauth_context,
+        anilist_client::{AniListClient, HttpClientSettings},
+        database::Database,
+        fairings::SecurityHeaders,
+        functions::{browser_binding_hash, verify_oauth_context},
+        lifecycle::Lifecycle,
+        pages::Pages,
+        rate_limit::RateLimits,
+        reload::{ReloadableSettings, SettingsHandle},
+        store::Storage,
         structs::{MyState, StateToken},
     };
 
     use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
     use chrono::Utc;
     use hmac::{Hmac, KeyInit, Mac};
-    use rocket::{Config, http::Status, local::asynchronous::Client, routes};
+    use rocket::{
+        Config, catchers,
+        http::{ContentType, Header, Status},
+        local::asynchronous::Client,
+        routes,
+    };
     use serde_json::json;
     use sha2::Sha256;
     use sqlx::{Pool, Postgres};
+    use std::net::SocketAddr;
     use url::Url;
 
     const TEST_CONTEXT_SECRET: &str = "test-oauth-context-secret-for-unit-tests";
 
     fn signed_start_url(discord_user_id: &str) -> String {
+        format!(
+            "/oauth/anilist/start?ctx={}",
+            signed_ctx(discord_user_id, json!({}))
+        )
+    }
+
+    fn signed_ctx(discord_user_id: &str, extra_claims: serde_json::Value) -> String {
         type HmacSha256 = Hmac<Sha256>;
 
         let now = Utc::now().timestamp();
-        let payload = json!({
+        let mut payload = json!({
             "v": 1,
             "discord_user_id": discord_user_id,
             "guild_id": "987654321098765432",
@@ -101,13 +305,16 @@ mod tests {
             "iat": now,
             "exp": now + 300,
         });
+        if let (Some(payload), Some(extra)) = (payload.as_object_mut(), extra_claims.as_object()) {
+            payload.extend(extra.clone());
+        }
         let payload_segment =
             URL_SAFE_NO_PAD.encode(serde_json::to_vec(&payload).expect("payload should serialize"));
         let mut mac = HmacSha256::new_from_slice(TEST_CONTEXT_SECRET.as_bytes()).expect("HMAC key");
         mac.update(payload_segment.as_bytes());
         let signature_segment = URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes());
 
-        format!("/oauth/anilist/start?ctx={payload_segment}.{signature_segment}")
+        format!("{payload_segment}.{signature_segment}")
     }
 
     #[get("/consume?<state>")]
@@ -116,50 +323,74 @@ mod tests {
         "ok"
     }
 
-    fn build_test_rocket(pool: Pool<Postgres>) -> rocket::Rocket<rocket::Build> {
-        let figment =
-            Config::figment().merge(("secret_key", "0123456789abcdef0123456789abcdef0123456789A="));
-
-        let state = MyState {
+    /// The state every start test uses; tests change single fields with struct update.
+    fn test_state(pool: Pool<Postgres>) -> MyState {
+        MyState {
             client_id: "client-id".to_string(),
-            client_secret: "client-secret".to_string(),
             redirect_uri: "http://127.0.0.1:8000/oauth/anilist/callback".to_string(),
-            context_signing_secret: TEST_CONTEXT_SECRET.to_string(),
             user_id_hash_salt: "test-userid-hash-salt".to_string(),
-            context_ttl_seconds: 300,
-            state_ttl_seconds: 600,
             token_endpoint: "https://anilist.co/api/v2/oauth/token".to_string(),
             user_endpoint: "https://graphql.anilist.co".to_string(),
-            client: reqwest::Client::new(),
-            pool,
-        };
+            client: AniListClient::default(),
+            storage: Storage::postgres(pool.clone()),
+            database: Some(Database::new(pool)),
+            settings: SettingsHandle::new(ReloadableSettings {
+                client_secret: "client-secret".to_string(),
+                context_signing_secret: TEST_CONTEXT_SECRET.to_string(),
+                context_ttl_seconds: 300,
+                state_ttl_seconds: 600,
+                admin_tokens: Vec::new(),
+            }),
+            rate_limits: RateLimits::per_minute(1_000, 1_000),
+            pages: Pages::embedded().clone(),
+            consent_page_enabled: false,
+            lifecycle: Lifecycle::default(),
+        }
+    }
+
+    fn test_rocket(state: MyState) -> rocket::Rocket<rocket::Build> {
+        // As in `main` without `CLIENT_IP_HEADER`: only the TCP peer counts.
+        let figment = Config::figment()
+            .merge(("secret_key", "0123456789abcdef0123456789abcdef0123456789A="))
+            .merge(("ip_header", false));
+
+        rocket::custom(figment).manage(state)
+    }
 
-        rocket::custom(figment)
-            .mount("/", routes![start])
-            .manage(state)
+    fn build_test_rocket(pool: Pool<Postgres>) -> rocket::Rocket<rocket::Build> {
+        test_rocket(test_state(pool)).mount("/", routes![start])
     }
 
     fn build_test_rocket_with_consume(pool: Pool<Postgres>) -> rocket::Rocket<rocket::Build> {
-        let figment =
-            Config::figment().merge(("secret_key", "0123456789abcdef0123456789abcdef0123456789A="));
+        test_rocket(test_state(pool)).mount("/", routes![start, consume])
+    }
 
-        let state = MyState {
-            client_id: "client-id".to_string(),
-            client_secret: "client-secret".to_string(),
-            redirect_uri: "http://127.0.0.1:8000/oauth/anilist/callback".to_string(),
-            context_signing_secret: TEST_CONTEXT_SECRET.to_string(),
-            user_id_hash_salt: "test-userid-hash-salt".to_string(),
-            context_ttl_seconds: 300,
-            state_ttl_seconds: 600,
-            token_endpoint: "https://anilist.co/api/v2/oauth/token".to_string(),
-            user_endpoint: "https://graphql.anilist.co".to_string(),
-            client: reqwest::Client::new(),
-            pool,
-        };
+    fn build_rate_limited_rocket(
+        pool: Pool<Postgres>,
+        rate_limits: RateLimits,
+    ) -> rocket::Rocket<rocket::Build> {
+        test_rocket(MyState {
+            rate_limits,
+            ..test_state(pool)
+        })
+        .mount("/", routes![start])
+        .register("/", catchers![too_many_requests])
+    }
+
+    fn build_consent_rocket(pool: Pool<Postgres>) -> rocket::Rocket<rocket::Build> {
+        test_rocket(MyState {
+            consent_page_enabled: true,
+            ..test_state(pool)
+        })
+        .mount("/", routes![start, confirm_start])
+        .attach(SecurityHeaders)
+    }
 
-        rocket::custom(figment)
-            .mount("/", routes![start, consume])
-            .manage(state)
+    async fn session_count(pool: &Pool<Postgres>) -> i64 {
+        sqlx::query_scalar("SELECT COUNT(*) FROM oauth_sessions")
+            .fetch_one(pool)
+            .await
+            .expect("session count should load")
     }
 
     #[sqlx::test(migrations = "./migrations")]
@@ -199,15 +430,120 @@ mod tests {
     }
 
     #[sqlx::test(migrations = "./migrations")]
-    async fn start_does_not_set_cookies(pool: Pool<Postgres>) {
+    async fn start_rejects_export_context(pool: Pool<Postgres>) {
+        let client = Client::tracked(build_test_rocket(pool.clone()))
+            .await
+            .expect("rocket client should build");
+        let ctx = signed_ctx("123456789", json!({ "purpose": "export" }));
+
+        let response = client
+            .get(format!("/oauth/anilist/start?ctx={ctx}"))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::BadRequest);
+        drop(response);
+        assert_eq!(session_count(&pool).await, 0);
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn start_uses_reloaded_signing_secret_and_state_ttl(pool: Pool<Postgres>) {
+        let client = Client::tracked(build_test_rocket(pool.clone()))
+            .await
+            .expect("rocket client should build");
+        let settings = &client
+            .rocket()
+            .state::<MyState>()
+            .expect("state should be managed")
+            .settings;
+        let rotated = |context_signing_secret: &str, state_ttl_seconds| ReloadableSettings {
+            context_signing_secret: context_signing_secret.to_string(),
+            state_ttl_seconds,
+            ..(*settings.current()).clone()
+        };
+
+        settings.replace(rotated("rotated-secret", 600));
+        let status = client
+            .get(signed_start_url("123456789"))
+            .dispatch()
+            .await
+            .status();
+        assert_eq!(status, Status::BadRequest);
+
+        settings.replace(rotated(TEST_CONTEXT_SECRET, 900));
+        let response = client.get(signed_start_url("123456789")).dispatch().await;
+        assert_eq!(response.status(), Status::SeeOther);
+        assert!(
+            response
+                .headers()
+                .get("Set-Cookie")
+                .any(|cookie| cookie.contains("Max-Age=900"))
+        );
+        drop(response);
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn start_shows_unavailable_page_while_anilist_circuit_is_open(pool: Pool<Postgres>) {
+        let rocket = build_test_rocket(pool.clone());
+        let state = rocket.state::<MyState>().expect("state should be managed");
+        for _ in 0..HttpClientSettings::default().breaker_failure_threshold {
+            state.client.breaker().record_failure();
+        }
+        let client = Client::tracked(rocket)
+            .await
+            .expect("rocket client should build");
+
+        let response = client.get(signed_start_url("123456789")).dispatch().await;
+
+        assert_eq!(response.status(), Status::ServiceUnavailable);
+        assert!(response.headers().get_one("Location").is_none());
+        let body = response.into_string().await.expect("body should exist");
+        assert!(body.contains("AniList is having issues right now"));
+        assert_eq!(session_count(&pool).await, 0);
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn start_sets_browser_binding_cookie(pool: Pool<Postgres>) {
         let client = Client::tracked(build_test_rocket(pool.clone()))
             .await
             .expect("rocket client should build");
         let response = client.get(signed_start_url("123456789")).dispatch().await;
 
+        let set_cookie = response
+            .headers()
+            .get_one("set-cookie")
+            .expect("start should set the browser binding cookie");
+        assert!(set_cookie.starts_with("annie_mei_oauth_binding="));
+        assert!(set_cookie.contains("HttpOnly"));
+        assert!(set_cookie.contains("SameSite=Lax"));
+        assert!(set_cookie.contains("Path=/oauth/anilist"));
+        assert!(set_cookie.contains("Max-Age=600"));
+
+        let binding = response
+            .cookies()
+            .get_private("annie_mei_oauth_binding")
+            .expect("binding cookie should decrypt");
+        let stored: Option<String> =
+            sqlx::query_scalar("SELECT browser_binding_hash FROM oauth_sessions")
+                .fetch_one(&pool)
+                .await
+                .expect("session should exist");
+        assert_eq!(
+            stored.as_deref(),
+            Some(browser_binding_hash(binding.value()).as_str())
+        );
         assert!(
-            response.headers().get_one("set-cookie").is_none(),
-            "start should not set any cookies"
+            !set_cookie.contains(binding.value()),
+            "cookie must be encrypted"
         );
 
         drop(response);
@@ -252,6 +588,210 @@ mod tests {
         pool.close().await;
     }
 
+    #[sqlx::test(migrations = "./migrations")]
+    async fn start_rate_limits_per_client_ip(pool: Pool<Postgres>) {
+        let client = Client::tracked(build_rate_limited_rocket(
+            pool.clone(),
+            RateLimits::per_minute(1, 1_000),
+        ))
+        .await
+        .expect("rocket client should build");
+        let remote: SocketAddr = "203.0.113.7:40000".parse().expect("valid socket address");
+
+        let first = client
+            .get(signed_start_url("111111111"))
+            .remote(remote)
+            .dispatch()
+            .await;
+        assert_eq!(first.status(), Status::SeeOther);
+        drop(first);
+
+        let limited = client
+            .get(signed_start_url("222222222"))
+            .remote(remote)
+            .dispatch()
+            .await;
+        assert_eq!(limited.status(), Status::TooManyRequests);
+        assert_eq!(limited.headers().get_one("Retry-After"), Some("60"));
+        let body = limited.into_string().await.expect("429 page should render");
+        assert!(body.contains("Too many attempts"));
+
+        let other_ip: SocketAddr = "198.51.100.9:40000".parse().expect("valid socket address");
+        let other = client
+            .get(signed_start_url("222222222"))
+            .remote(other_ip)
+            .dispatch()
+            .await;
+        assert_eq!(other.status(), Status::SeeOther);
+        drop(other);
+
+        let sessions: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM oauth_sessions")
+            .fetch_one(&pool)
+            .await
+            .expect("session count should load");
+        assert_eq!(sessions, 2, "limited requests must not create sessions");
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn start_rate_limit_ignores_client_supplied_ip_headers(pool: Pool<Postgres>) {
+        let client = Client::tracked(build_rate_limited_rocket(
+            pool.clone(),
+            RateLimits::per_minute(1, 1_000),
+        ))
+        .await
+        .expect("rocket client should build");
+        let remote: SocketAddr = "203.0.113.7:40000".parse().expect("valid socket address");
+
+        let first = client
+            .get(signed_start_url("111111111"))
+            .remote(remote)
+            .header(Header::new("X-Real-IP", "198.51.100.1"))
+            .dispatch()
+            .await;
+        assert_eq!(first.status(), Status::SeeOther);
+        drop(first);
+
+        let spoofed = client
+            .get(signed_start_url("222222222"))
+            .remote(remote)
+            .header(Header::new("X-Real-IP", "198.51.100.2"))
+            .dispatch()
+            .await;
+        assert_eq!(spoofed.status(), Status::TooManyRequests);
+        drop(spoofed);
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn start_rate_limits_requests_without_a_client_address(pool: Pool<Postgres>) {
+        let client = Client::tracked(build_rate_limited_rocket(
+            pool.clone(),
+            RateLimits::per_minute(1, 1_000),
+        ))
+        .await
+        .expect("rocket client should build");
+
+        let first = client.get(signed_start_url("111111111")).dispatch().await;
+        assert_eq!(first.status(), Status::SeeOther);
+        drop(first);
+
+        let limited = client.get(signed_start_url("222222222")).dispatch().await;
+        assert_eq!(limited.status(), Status::TooManyRequests);
+        drop(limited);
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn start_rate_limits_per_discord_user(pool: Pool<Postgres>) {
+        let client = Client::tracked(build_rate_limited_rocket(
+            pool.clone(),
+            RateLimits::per_minute(1_000, 1),
+        ))
+        .await
+        .expect("rocket client should build");
+
+        let first = client.get(signed_start_url("333333333")).dispatch().await;
+        assert_eq!(first.status(), Status::SeeOther);
+        drop(first);
+
+        let limited = client.get(signed_start_url("333333333")).dispatch().await;
+        assert_eq!(limited.status(), Status::TooManyRequests);
+        drop(limited);
+
+        let other_user = client.get(signed_start_url("444444444")).dispatch().await;
+        assert_eq!(other_user.status(), Status::SeeOther);
+
+        drop(other_user);
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn start_shows_consent_page_without_creating_session(pool: Pool<Postgres>) {
+        let client = Client::tracked(build_consent_rocket(pool.clone()))
+            .await
+            .expect("rocket client should build");
+        let ctx = signed_ctx(
+            "123456789",
+            json!({ "discord_username": "<annie>", "discord_avatar": "a_1b2c3d" }),
+        );
+
+        let response = client
+            .get(format!("/oauth/anilist/start?ctx={ctx}"))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        let csp = response
+            .headers()
+            .get_one("Content-Security-Policy")
+            .expect("consent page should set a CSP")
+            .to_string();
+        assert!(csp.contains("form-action 'self' https://anilist.co"));
+        assert!(
+            response.headers().get_one("set-cookie").is_none(),
+            "the consent page should not bind the browser until Continue"
+        );
+        let body = response
+            .into_string()
+            .await
+            .expect("consent page should render");
+        assert!(body.contains("&lt;annie&gt;"));
+        assert!(body.contains("Discord ID: 123456789"));
+        // minijinja escapes `/` in attribute values; browsers decode it back.
+        assert!(body.contains("cdn.discordapp.com&#x2f;avatars&#x2f;123456789&#x2f;a_1b2c3d.png"));
+        assert!(body.contains(&format!(r#"name="ctx" value="{ctx}""#)));
+        assert!(body.contains(r#"<form method="post" action="/oauth/anilist/start">"#));
+        assert_eq!(session_count(&pool).await, 0);
+
+        drop(client);
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn consent_continue_creates_session_and_redirects(pool: Pool<Postgres>) {
+        let client = Client::tracked(build_consent_rocket(pool.clone()))
+            .await
+            .expect("rocket client should build");
+        let ctx = signed_ctx("123456789", json!({}));
+
+        let response = client
+            .post("/oauth/anilist/start")
+            .header(ContentType::Form)
+            .body(format!("ctx={ctx}"))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::SeeOther);
+        let location = response
+            .headers()
+            .get_one("location")
+            .expect("continue should redirect");
+        assert!(location.starts_with("https://anilist.co/api/v2/oauth/authorize"));
+        drop(response);
+        assert_eq!(session_count(&pool).await, 1);
+
+        let rejected = client
+            .post("/oauth/anilist/start")
+            .header(ContentType::Form)
+            .body("ctx=not-a-valid-context")
+            .dispatch()
+            .await;
+        assert_eq!(rejected.status(), Status::BadRequest);
+        drop(rejected);
+        assert_eq!(session_count(&pool).await, 1);
+
+        drop(client);
+        pool.close().await;
+    }
+
     #[test]
     fn verify_rejects_expired_context() {
         type HmacSha256 = Hmac<Sha256>;
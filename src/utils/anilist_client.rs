@@ -0,0 +1,446 @@
+use super::circuit_breaker::CircuitBreaker;
+
+use rand::Rng;
+use reqwest::{RequestBuilder, Response, StatusCode, header::RETRY_AFTER};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tracing::Instrument;
+
+/// Timeouts and retry budget for requests to AniList.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpClientSettings {
+    pub connect_timeout: Duration,
+    /// Whole-request timeout, covering the wait for and read of the response.
+    pub request_timeout: Duration,
+    /// Retries after the first attempt; `0` disables retrying.
+    pub max_retries: u32,
+    pub base_backoff: Duration,
+    /// Cap on any single wait, including `Retry-After`. A longer `Retry-After` is not
+    /// waited out and the `429` is returned to the caller.
+    pub max_backoff: Duration,
+    /// Consecutive failed calls (after retries) that open the circuit breaker.
+    pub breaker_failure_threshold: u32,
+    pub breaker_open_duration: Duration,
+}
+
+impl Default for HttpClientSettings {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(5),
+            request_timeout: Duration::from_secs(10),
+            max_retries: 2,
+            base_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(5),
+            breaker_failure_threshold: 5,
+            breaker_open_duration: Duration::from_secs(60),
+        }
+    }
+}
+
+/// How long a reachability result is reused, so readiness checks do not hit AniList
+/// on every poll.
+const REACHABILITY_CACHE_TTL: Duration = Duration::from_secs(30);
+const REACHABILITY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Whether a request can be repeated after it may have reached AniList.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Idempotency {
+    /// Reads such as the `Viewer` query: retried on timeouts and `5xx` too.
+    Idempotent,
+    /// Single-use requests such as the code exchange: only retried when AniList
+    /// cannot have acted on them (connection failures and `429`).
+    NonIdempotent,
+}
+
+/// `reqwest::Client` wrapper for outbound AniList calls with timeouts, retries,
+/// jittered exponential backoff and a circuit breaker shared by all clones.
+#[derive(Debug, Clone)]
+pub struct AniListClient {
+    http: reqwest::Client,
+    settings: HttpClientSettings,
+    breaker: Arc<CircuitBreaker>,
+    reachability: Arc<Mutex<Option<(Instant, bool)>>>,
+}
+
+impl AniListClient {
+    pub fn new(settings: HttpClientSettings) -> reqwest::Result<Self> {
+        let http = reqwest::Client::builder()
+            .user_agent(concat!(
+                env!("CARGO_PKG_NAME"),
+                "/",
+                env!("CARGO_PKG_VERSION")
+            ))
+            .connect_timeout(settings.connect_timeout)
+            .timeout(settings.request_timeout)
+            .build()?;
+
+        Ok(Self::with_http(http, settings))
+    }
+
+    pub fn with_http(http: reqwest::Client, settings: HttpClientSettings) -> Self {
+        let breaker = CircuitBreaker::new(
+            settings.breaker_failure_threshold,
+            settings.breaker_open_duration,
+        );
+
+        Self {
+            http,
+            settings,
+            breaker: Arc::new(breaker),
+            reachability: Arc::default(),
+        }
+    }
+
+    pub fn breaker(&self) -> &CircuitBreaker {
+        &self.breaker
+    }
+
+    /// Whether `url` answers at all, cached for a short while. Any HTTP status counts
+    /// as reachable. Bypasses retries and the circuit breaker.
+    pub async fn is_reachable(&self, url: &str) -> bool {
+        if let Some((checked_at, reachable)) = *self
+            .reachability
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            && checked_at.elapsed() < REACHABILITY_CACHE_TTL
+        {
+            return reachable;
+        }
+
+        let reachable = self
+            .http
+            .head(url)
+            .timeout(REACHABILITY_TIMEOUT)
+            .send()
+            .await
+            .is_ok();
+        *self
+            .reachability
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some((Instant::now(), reachable));
+
+        reachable
+    }
+
+    /// Sends the request produced by `build`, rebuilding it for every attempt. The
+    /// final response is returned whatever its status, so callers keep their own
+    /// status handling. Transport errors, `429` and `5xx` count against the circuit
+    /// breaker; anything else resets it.
+    pub async fn send<F>(
+        &self,
+        operation: &'static str,
+        idempotency: Idempotency,
+        build: F,
+    ) -> Result<Response, reqwest::Error>
+    where
+        F: Fn(&reqwest::Client) -> RequestBuilder,
+    {
+        let result = self.send_with_retries(operation, idempotency, build).await;
+
+        match &result {
+            Ok(response)
+                if response.status() != StatusCode::TOO_MANY_REQUESTS
+                    && !response.status().is_server_error() =>
+            {
+                self.breaker.record_success()
+            }
+            _ => self.breaker.record_failure(),
+        }
+
+        result
+    }
+
+    async fn send_with_retries<F>(
+        &self,
+        operation: &'static str,
+        idempotency: Idempotency,
+        build: F,
+    ) -> Result<Response, reqwest::Error>
+    where
+        F: Fn(&reqwest::Client) -> RequestBuilder,
+    {
+        let mut attempt = 0;
+
+        loop {
+            let span = tracing::info_span!(
+                "anilist.request",
+                operation,
+                attempt,
+                status = tracing::field::Empty
+            );
+            let result = build(&self.http).send().instrument(span.clone()).await;
+            if let Ok(response) = &result {
+                span.record("status", response.status().as_u16());
+            }
+
+            if attempt >= self.settings.max_retries {
+                return result;
+            }
+            let Some(delay) = self.retry_delay(&result, idempotency, attempt) else {
+                return result;
+            };
+
+            match &result {
+                Ok(response) => warn!(
+                    "AniList {operation} returned {}; retrying in {}ms",
+                    response.status(),
+                    delay.as_millis()
+                ),
+                Err(_) => warn!(
+                    "AniList {operation} request failed; retrying in {}ms",
+                    delay.as_millis()
+                ),
+            }
+
+            rocket::tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    fn retry_delay(
+        &self,
+        result: &Result<Response, reqwest::Error>,
+        idempotency: Idempotency,
+        attempt: u32,
+    ) -> Option<Duration> {
+        let idempotent = idempotency == Idempotency::Idempotent;
+
+        match result {
+            Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS => {
+                match retry_after(response) {
+                    Some(wait) if wait > self.settings.max_backoff => None,
+                    Some(wait) => Some(wait),
+                    None => Some(self.backoff(attempt)),
+                }
+            }
+            Ok(response) if idempotent && is_transient(response.status()) => {
+                Some(self.backoff(attempt))
+            }
+            Ok(_) => None,
+            Err(e) if e.is_connect() || idempotent => Some(self.backoff(attempt)),
+            Err(_) => None,
+        }
+    }
+
+    /// Full jitter: a random wait up to `base_backoff * 2^attempt`, capped.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .settings
+            .base_backoff
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.settings.max_backoff);
+
+        rand::rng().random_range(Duration::ZERO..=ceiling)
+    }
+}
+
+impl Default for AniListClient {
+    fn default() -> Self {
+        Self::with_http(reqwest::Client::new(), HttpClientSettings::default())
+    }
+}
+
+const fn is_transient(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// AniList sends `Retry-After` in whole seconds.
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AniListClient, HttpClientSettings, Idempotency};
+    use std::time::Duration;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{method, path},
+    };
+
+    fn fast_client(max_retries: u32) -> AniListClient {
+        AniListClient::new(HttpClientSettings {
+            connect_timeout: Duration::from_secs(1),
+            request_timeout: Duration::from_millis(500),
+            max_retries,
+            base_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_secs(1),
+            ..HttpClientSettings::default()
+        })
+        .expect("client should build")
+    }
+
+    #[rocket::async_test]
+    async fn retries_rate_limited_requests_after_retry_after() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "0"))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/graphql"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let url = format!("{}/graphql", server.uri());
+        let response = fast_client(2)
+            .send("viewer", Idempotency::NonIdempotent, |http| http.post(&url))
+            .await
+            .expect("request should succeed");
+
+        assert_eq!(response.status(), 200);
+    }
+
+    #[rocket::async_test]
+    async fn does_not_wait_out_long_retry_after() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "60"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let response = fast_client(2)
+            .send("viewer", Idempotency::Idempotent, |http| {
+                http.post(server.uri())
+            })
+            .await
+            .expect("request should complete");
+
+        assert_eq!(response.status(), 429);
+    }
+
+    #[rocket::async_test]
+    async fn retries_server_errors_only_when_idempotent() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/idempotent"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(3)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = fast_client(2);
+        let idempotent = format!("{}/idempotent", server.uri());
+        let response = client
+            .send("viewer", Idempotency::Idempotent, |http| {
+                http.post(&idempotent)
+            })
+            .await
+            .expect("request should complete");
+        assert_eq!(response.status(), 503);
+
+        let token = format!("{}/token", server.uri());
+        let response = client
+            .send("token", Idempotency::NonIdempotent, |http| {
+                http.post(&token)
+            })
+            .await
+            .expect("request should complete");
+        assert_eq!(response.status(), 503);
+    }
+
+    #[rocket::async_test]
+    async fn retries_timeouts_when_idempotent() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(2)))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let error = fast_client(1)
+            .send("viewer", Idempotency::Idempotent, |http| {
+                http.post(server.uri())
+            })
+            .await
+            .expect_err("request should time out");
+
+        assert!(error.is_timeout());
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let client = AniListClient::with_http(
+            reqwest::Client::new(),
+            HttpClientSettings {
+                base_backoff: Duration::from_millis(100),
+                max_backoff: Duration::from_millis(300),
+                ..HttpClientSettings::default()
+            },
+        );
+
+        for attempt in 0..10 {
+            assert!(client.backoff(attempt) <= Duration::from_millis(300));
+        }
+    }
+
+    #[rocket::async_test]
+    async fn upstream_failures_open_the_circuit() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/down"))
+            .respond_with(ResponseTemplate::new(502))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/denied"))
+            .respond_with(ResponseTemplate::new(400))
+            .mount(&server)
+            .await;
+
+        let client = AniListClient::new(HttpClientSettings {
+            max_retries: 0,
+            breaker_failure_threshold: 2,
+            ..HttpClientSettings::default()
+        })
+        .expect("client should build");
+        let shared = client.clone();
+        let down = format!("{}/down", server.uri());
+        let denied = format!("{}/denied", server.uri());
+
+        for _ in 0..2 {
+            let _ = client
+                .send("token", Idempotency::NonIdempotent, |http| {
+                    http.post(&denied)
+                })
+                .await;
+            let _ = client
+                .send("token", Idempotency::NonIdempotent, |http| http.post(&down))
+                .await;
+        }
+        assert!(client.breaker().allows_requests());
+
+        let _ = client
+            .send("token", Idempotency::NonIdempotent, |http| http.post(&down))
+            .await;
+        assert!(!shared.breaker().allows_requests());
+    }
+}
@@ -0,0 +1,216 @@
+use super::metrics::{ANILIST_CIRCUIT_STATE, ANILIST_CIRCUIT_TRIPS_TOTAL};
+
+use serde::Serialize;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    /// The open period has elapsed; the next request is a trial.
+    HalfOpen,
+    Open,
+}
+
+impl CircuitState {
+    /// Value of the `annie_mei_anilist_circuit_state` gauge.
+    const fn gauge_value(self) -> f64 {
+        match self {
+            Self::Closed => 0.0,
+            Self::HalfOpen => 1.0,
+            Self::Open => 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    /// When the half-open trial was handed out. Another trial is allowed if it has not
+    /// reported back within `open_duration`, e.g. a user who never finished logging in.
+    trial_started_at: Option<Instant>,
+}
+
+/// Tracks consecutive AniList failures. After `failure_threshold` of them the circuit
+/// opens for `open_duration`; then a single trial request is let through, and its
+/// success closes the circuit while its failure reopens it.
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_duration: Duration,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, open_duration: Duration) -> Self {
+        metrics::gauge!(ANILIST_CIRCUIT_STATE).set(CircuitState::Closed.gauge_value());
+
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            open_duration,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn state_of(&self, inner: &Inner) -> CircuitState {
+        match inner.opened_at {
+            None => CircuitState::Closed,
+            Some(opened_at) if opened_at.elapsed() < self.open_duration => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.state_of(&self.lock())
+    }
+
+    /// `false` while the circuit is open and users should not be sent to AniList. In
+    /// half-open, only the first caller gets `true`; it is the trial.
+    pub fn allows_requests(&self) -> bool {
+        let mut inner = self.lock();
+        match self.state_of(&inner) {
+            CircuitState::Closed => true,
+            CircuitState::Open => false,
+            CircuitState::HalfOpen => {
+                if inner
+                    .trial_started_at
+                    .is_some_and(|started_at| started_at.elapsed() < self.open_duration)
+                {
+                    return false;
+                }
+                inner.trial_started_at = Some(Instant::now());
+                true
+            }
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut inner = self.lock();
+        if inner.opened_at.take().is_some() {
+            info!("AniList circuit breaker closed");
+        }
+        inner.consecutive_failures = 0;
+        inner.trial_started_at = None;
+        metrics::gauge!(ANILIST_CIRCUIT_STATE).set(CircuitState::Closed.gauge_value());
+    }
+
+    /// Failures while the circuit is already open neither count as a trip nor extend
+    /// the open period.
+    pub fn record_failure(&self) {
+        let mut inner = self.lock();
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+        let trips = match self.state_of(&inner) {
+            CircuitState::Closed => inner.consecutive_failures >= self.failure_threshold,
+            CircuitState::HalfOpen => true,
+            CircuitState::Open => false,
+        };
+        if !trips {
+            return;
+        }
+
+        inner.opened_at = Some(Instant::now());
+        inner.trial_started_at = None;
+        metrics::gauge!(ANILIST_CIRCUIT_STATE).set(CircuitState::Open.gauge_value());
+        metrics::counter!(ANILIST_CIRCUIT_TRIPS_TOTAL).increment(1);
+        warn!(
+            "AniList circuit breaker opened after {} consecutive failures",
+            inner.consecutive_failures
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CircuitBreaker, CircuitState};
+    use crate::utils::metrics::ANILIST_CIRCUIT_TRIPS_TOTAL;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(60));
+
+        breaker.record_failure();
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(!breaker.allows_requests());
+    }
+
+    #[test]
+    fn half_open_trial_closes_or_reopens() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.allows_requests());
+
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60));
+        breaker.record_failure();
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+    }
+
+    #[test]
+    fn half_open_lets_one_trial_through() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60));
+        breaker.record_failure();
+        breaker.lock().opened_at = Some(Instant::now() - Duration::from_secs(61));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        assert!(breaker.allows_requests());
+        assert!(!breaker.allows_requests(), "only one trial at a time");
+
+        breaker.record_success();
+        assert!(breaker.allows_requests());
+        assert!(breaker.allows_requests());
+    }
+
+    #[test]
+    fn failures_while_open_do_not_count_as_trips() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+        let _guard = metrics::set_default_local_recorder(&recorder);
+
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+        breaker.record_failure();
+        breaker.record_failure();
+        let opened_at = breaker.lock().opened_at;
+        breaker.record_failure();
+        breaker.record_failure();
+        assert_eq!(
+            breaker.lock().opened_at,
+            opened_at,
+            "open period must not extend"
+        );
+
+        breaker.lock().opened_at = Some(Instant::now() - Duration::from_secs(61));
+        assert!(breaker.allows_requests());
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        assert!(
+            handle
+                .render()
+                .contains(&format!("{ANILIST_CIRCUIT_TRIPS_TOTAL} 2"))
+        );
+    }
+}
@@ -1,3 +1,8 @@
+pub const ANILIST_ORIGIN: &str = "https://anilist.co";
 pub const ANILIST_AUTH: &str = "https://anilist.co/api/v2/oauth/authorize";
 pub const ANILIST_TOKEN: &str = "https://anilist.co/api/v2/oauth/token";
 pub const ANILIST_USER_BASE: &str = "https://graphql.anilist.co";
+pub const DISCORD_CDN: &str = "https://cdn.discordapp.com";
+pub const BROWSER_BINDING_COOKIE: &str = "annie_mei_oauth_binding";
+pub const BINDING_COOKIE_PATH: &str = "/oauth/anilist";
+pub const CONTEXT_PURPOSE_EXPORT: &str = "export";
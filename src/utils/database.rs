@@ -0,0 +1,163 @@
+use sqlx::{
+    PgPool,
+    migrate::{MigrateError, Migrator},
+    postgres::{PgConnectOptions, PgPoolOptions},
+};
+use std::time::Duration;
+
+#[derive(Clone)]
+pub struct Database {
+    pub pool: PgPool,
+    /// Replica for read-only admin lookups; the primary when no replica is configured.
+    pub read_pool: PgPool,
+}
+
+impl Database {
+    /// Reads and writes both go to `pool`.
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            read_pool: pool.clone(),
+            pool,
+        }
+    }
+}
+
+/// Pool sizing and timeouts, shared by the primary and the read replica.
+#[derive(Debug, Clone, Copy)]
+pub struct PoolSettings {
+    pub max_connections: u32,
+    pub min_connections: u32,
+    /// How long a request waits for a free connection before failing.
+    pub acquire_timeout: Duration,
+    pub idle_timeout: Duration,
+    /// Postgres `statement_timeout` for every connection; zero disables it.
+    pub statement_timeout: Duration,
+}
+
+impl Default for PoolSettings {
+    fn default() -> Self {
+        Self {
+            max_connections: 5,
+            min_connections: 0,
+            acquire_timeout: Duration::from_secs(10),
+            idle_timeout: Duration::from_secs(600),
+            statement_timeout: Duration::from_secs(15),
+        }
+    }
+}
+
+impl PoolSettings {
+    pub async fn connect(&self, url: &str) -> Result<PgPool, sqlx::Error> {
+        self.connect_with(url.parse()?).await
+    }
+
+    pub async fn connect_with(&self, options: PgConnectOptions) -> Result<PgPool, sqlx::Error> {
+        let options = if self.statement_timeout.is_zero() {
+            options
+        } else {
+            let timeout = format!("{}ms", self.statement_timeout.as_millis());
+            options.options([("statement_timeout", timeout.as_str())])
+        };
+
+        PgPoolOptions::new()
+            .max_connections(self.max_connections)
+            .min_connections(self.min_connections.min(self.max_connections))
+            .acquire_timeout(self.acquire_timeout)
+            .idle_timeout(self.idle_timeout)
+            .connect_with(options)
+            .await
+    }
+}
+
+/// Applies `migrator` over a separate connection without `statement_timeout`, so a
+/// long index build is not cancelled.
+pub async fn run_migrations(migrator: &Migrator, pool: &PgPool) -> Result<(), MigrateError> {
+    // Postgres applies `-c` options in order, so this overrides the pool's timeout.
+    let options = (*pool.connect_options())
+        .clone()
+        .options([("statement_timeout", "0")]);
+    let migration_pool = PgPoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await?;
+    let result = migrator.run(&migration_pool).await;
+    migration_pool.close().await;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PoolSettings, run_migrations};
+    use sqlx::{
+        migrate::Migrator,
+        postgres::{PgConnectOptions, PgPoolOptions},
+    };
+    use std::time::Duration;
+
+    #[sqlx::test]
+    async fn statement_timeout_cancels_slow_queries(
+        _pool_options: PgPoolOptions,
+        connect_options: PgConnectOptions,
+    ) {
+        let settings = PoolSettings {
+            statement_timeout: Duration::from_millis(100),
+            ..PoolSettings::default()
+        };
+        let pool = settings
+            .connect_with(connect_options)
+            .await
+            .expect("pool should connect");
+
+        let error = sqlx::query("SELECT pg_sleep(2)")
+            .execute(&pool)
+            .await
+            .expect_err("slow query should be cancelled");
+        let code = error
+            .as_database_error()
+            .and_then(|error| error.code())
+            .map(|code| code.into_owned());
+        assert_eq!(code.as_deref(), Some("57014"));
+
+        sqlx::query("SELECT 1")
+            .execute(&pool)
+            .await
+            .expect("fast queries should still run");
+
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn migrations_ignore_the_statement_timeout(
+        _pool_options: PgPoolOptions,
+        connect_options: PgConnectOptions,
+    ) {
+        let settings = PoolSettings {
+            statement_timeout: Duration::from_millis(100),
+            ..PoolSettings::default()
+        };
+        let pool = settings
+            .connect_with(connect_options)
+            .await
+            .expect("pool should connect");
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        std::fs::write(
+            dir.path().join("1_slow.sql"),
+            "SELECT pg_sleep(0.5); CREATE TABLE slow_migration (id BIGINT);",
+        )
+        .expect("migration should be written");
+        let migrator = Migrator::new(dir.path())
+            .await
+            .expect("migrations should load");
+
+        run_migrations(&migrator, &pool)
+            .await
+            .expect("slow migration should finish");
+
+        sqlx::query("SELECT pg_sleep(2)")
+            .execute(&pool)
+            .await
+            .expect_err("pooled connections should keep the timeout");
+
+        pool.close().await;
+    }
+}
@@ -0,0 +1,185 @@
+use rocket::{
+    Config, Data, Request, Response,
+    fairing::{Fairing, Info, Kind},
+    http::Header,
+};
+
+use super::{
+    consts::{ANILIST_ORIGIN, DISCORD_CDN},
+    functions::get_state_token,
+    structs::{CspNonce, RequestId},
+};
+
+const HSTS_VALUE: &str = "max-age=63072000; includeSubDomains";
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+const MAX_REQUEST_ID_LENGTH: usize = 64;
+
+/// Adds CSP, framing, referrer and (in the release profile) HSTS headers to every response.
+pub struct SecurityHeaders;
+
+/// The callback URL carries the authorization code, so nothing may be loaded or
+/// navigated to that could receive it via `Referer`. The only exceptions are Discord
+/// avatars and the consent form, which posts here and is redirected to AniList.
+fn content_security_policy(nonce: &str) -> String {
+    format!(
+        "default-src 'none'; style-src 'nonce-{nonce}'; img-src 'self' {DISCORD_CDN}; \
+         base-uri 'none'; form-action 'self' {ANILIST_ORIGIN}; frame-ancestors 'none'"
+    )
+}
+
+#[rocket::async_trait]
+impl Fairing for SecurityHeaders {
+    fn info(&self) -> Info {
+        Info {
+            name: "Security headers",
+            kind: Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        let nonce = CspNonce::for_request(req);
+
+        res.set_header(Header::new(
+            "Content-Security-Policy",
+            content_security_policy(&nonce.0),
+        ));
+        res.set_header(Header::new("Referrer-Policy", "no-referrer"));
+        res.set_header(Header::new("X-Frame-Options", "DENY"));
+        res.set_header(Header::new("X-Content-Type-Options", "nosniff"));
+
+        if req.rocket().config().profile == Config::RELEASE_PROFILE {
+            res.set_header(Header::new("Strict-Transport-Security", HSTS_VALUE));
+        }
+    }
+}
+
+/// Accepts a well-formed incoming `X-Request-Id` (or generates one), caches it as
+/// the request's [`RequestId`] and echoes it on the response.
+pub struct RequestIdHeader;
+
+fn is_valid_request_id(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= MAX_REQUEST_ID_LENGTH
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+#[rocket::async_trait]
+impl Fairing for RequestIdHeader {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request ID",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, req: &mut Request<'_>, _data: &mut Data<'_>) {
+        let incoming = req
+            .headers()
+            .get_one(REQUEST_ID_HEADER)
+            .filter(|value| is_valid_request_id(value))
+            .map(str::to_string);
+
+        req.local_cache(|| RequestId(incoming.unwrap_or_else(get_state_token)));
+    }
+
+    async fn on_response<'r>(&self, req: &'r Request<'_>, res: &mut Response<'r>) {
+        res.set_header(Header::new(
+            REQUEST_ID_HEADER,
+            RequestId::for_request(req).0,
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{REQUEST_ID_HEADER, RequestIdHeader, SecurityHeaders, is_valid_request_id};
+    use crate::utils::structs::RequestId;
+
+    use rocket::{Config, http::Header, local::asynchronous::Client};
+
+    #[get("/")]
+    fn index() -> &'static str {
+        "ok"
+    }
+
+    #[get("/request-id")]
+    fn request_id(request_id: RequestId) -> String {
+        request_id.0
+    }
+
+    async fn hsts_header(profile: rocket::figment::Profile) -> Option<String> {
+        // The release profile refuses to launch without an explicit secret key.
+        let figment = Config::figment()
+            .select(profile)
+            .merge(("secret_key", "0123456789abcdef0123456789abcdef0123456789A="));
+        let rocket = rocket::custom(figment)
+            .mount("/", routes![index])
+            .attach(SecurityHeaders);
+        let client = Client::tracked(rocket)
+            .await
+            .expect("rocket client should build");
+        let response = client.get("/").dispatch().await;
+
+        response
+            .headers()
+            .get_one("Strict-Transport-Security")
+            .map(str::to_string)
+    }
+
+    #[rocket::async_test]
+    async fn hsts_is_only_sent_in_release_profile() {
+        assert!(hsts_header(Config::DEBUG_PROFILE).await.is_none());
+        assert!(
+            hsts_header(Config::RELEASE_PROFILE)
+                .await
+                .is_some_and(|value| value.contains("max-age="))
+        );
+    }
+
+    #[rocket::async_test]
+    async fn request_id_is_echoed_or_generated() {
+        let rocket = rocket::build()
+            .mount("/", routes![request_id])
+            .attach(RequestIdHeader);
+        let client = Client::tracked(rocket)
+            .await
+            .expect("rocket client should build");
+
+        let echoed = client
+            .get("/request-id")
+            .header(Header::new(REQUEST_ID_HEADER, "support-ticket-42"))
+            .dispatch()
+            .await;
+        assert_eq!(
+            echoed.headers().get_one(REQUEST_ID_HEADER),
+            Some("support-ticket-42")
+        );
+        assert_eq!(
+            echoed.into_string().await.as_deref(),
+            Some("support-ticket-42")
+        );
+
+        let replaced = client
+            .get("/request-id")
+            .header(Header::new(REQUEST_ID_HEADER, "<script>"))
+            .dispatch()
+            .await;
+        let generated = replaced
+            .headers()
+            .get_one(REQUEST_ID_HEADER)
+            .expect("request ID should be generated")
+            .to_string();
+        assert_ne!(generated, "<script>");
+        assert_eq!(replaced.into_string().await, Some(generated));
+    }
+
+    #[test]
+    fn request_id_validation_rejects_unsafe_values() {
+        assert!(is_valid_request_id("abc-123_DEF.4"));
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id("has space"));
+        assert!(!is_valid_request_id(&"a".repeat(65)));
+    }
+}
@@ -1,9 +1,13 @@
+use crate::utils::anilist_client::{AniListClient, Idempotency};
+use crate::utils::consts::DISCORD_CDN;
+use crate::utils::i18n::{Locale, MessageKey};
 use crate::utils::observability::{
     configure_oauth_scope, identifier_fingerprint, record_identifier_fingerprint,
 };
 use crate::utils::structs::{
-    OAuthContextPayload, OAuthCredential, OAuthSession, TokenErrorResponse, TokenResponse,
-    ViewerResponse,
+    AuditEvent, CredentialHealth, CredentialSearch, CredentialSummary, MigrationState,
+    MigrationStatus, OAuthContextPayload, OAuthCredential, OAuthSession, RelinkReason,
+    SessionSummary, TokenErrorResponse, TokenResponse, UserDataExport, ViewerResponse,
 };
 
 use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
@@ -13,14 +17,28 @@ use nanoid::nanoid;
 use rocket::http::Status;
 use serde_json::json;
 use sha2::Sha256;
-use sqlx::{Pool, Postgres};
+use sqlx::{Pool, Postgres, migrate::Migrator};
+use std::collections::BTreeMap;
+
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
 
 const CONTEXT_VERSION: u8 = 1;
 const MAX_CONTEXT_FUTURE_SKEW_SECONDS: i64 = 60;
 const DEFAULT_ANILIST_ACCESS_TOKEN_TTL_SECONDS: i64 = 31_536_000;
-const RELINK_REASON_TOKEN_EXPIRED: &str = "token_expired";
+pub const RELINK_REASON_TOKEN_EXPIRED: &str = "token_expired";
+/// AniList rejected the stored token, e.g. because the user revoked the app.
+pub const RELINK_REASON_TOKEN_REVOKED: &str = "token_revoked";
+/// The token now belongs to a different AniList account than the one we linked.
+pub const RELINK_REASON_ANILIST_ID_MISMATCH: &str = "anilist_id_mismatch";
+
+pub const AUDIT_EVENT_LINKED: &str = "linked";
+pub const AUDIT_EVENT_RELINK_REQUIRED: &str = "relink_required";
+pub const AUDIT_EVENT_UNLINKED: &str = "unlinked";
+pub const AUDIT_EVENT_EXPORTED: &str = "exported";
+pub const AUDIT_ACTOR_USER: &str = "user";
+pub const AUDIT_ACTOR_SYSTEM: &str = "system";
 
-pub const RELINK_REQUIRED_MESSAGE: &str = "Your AniList link has expired or needs to be reconnected. Please run `/register` again in Discord.";
+pub const RELINK_REQUIRED_MESSAGE: &str = MessageKey::RelinkRequired.text(Locale::En);
 
 #[derive(Debug)]
 pub enum UpsertOAuthCredentialsError {
@@ -30,14 +48,14 @@ pub enum UpsertOAuthCredentialsError {
 
 #[derive(Debug)]
 pub enum TokenExchangeError {
-    BadRequest(String),
-    BadGateway(String),
+    BadRequest(MessageKey),
+    BadGateway(MessageKey),
 }
 
 impl TokenExchangeError {
-    pub fn message(&self) -> &str {
+    pub fn message_key(&self) -> MessageKey {
         match self {
-            Self::BadRequest(message) | Self::BadGateway(message) => message.as_str(),
+            Self::BadRequest(key) | Self::BadGateway(key) => *key,
         }
     }
 
@@ -51,23 +69,33 @@ impl TokenExchangeError {
 
 #[derive(Debug)]
 pub enum ViewerFetchError {
-    BadGateway(String),
+    /// AniList answered `401`: the token was revoked or the account deleted.
+    Unauthorized,
+    BadGateway(MessageKey),
 }
 
 impl ViewerFetchError {
-    pub fn message(&self) -> &str {
+    pub fn message_key(&self) -> MessageKey {
         match self {
-            Self::BadGateway(message) => message.as_str(),
+            Self::Unauthorized => MessageKey::ViewerRequestFailed,
+            Self::BadGateway(key) => *key,
         }
     }
 
     pub fn status(&self) -> Status {
         match self {
-            Self::BadGateway(_) => Status::BadGateway,
+            Self::Unauthorized | Self::BadGateway(_) => Status::BadGateway,
         }
     }
 }
 
+#[derive(Debug)]
+pub enum CredentialVerificationError {
+    Missing,
+    Upstream(ViewerFetchError),
+    Db(sqlx::Error),
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum OAuthContextError {
     Malformed,
@@ -83,7 +111,7 @@ pub enum OAuthContextError {
 
 #[tracing::instrument(skip(client, access_token))]
 pub async fn fetch_viewer_id(
-    client: &reqwest::Client,
+    client: &AniListClient,
     user_endpoint: &str,
     access_token: &str,
     discord_user_fingerprint: Option<&str>,
@@ -97,10 +125,11 @@ pub async fn fetch_viewer_id(
     ";
 
     let viewer_response = client
-        .post(user_endpoint)
-        .bearer_auth(access_token)
-        .json(&json!({ "query": USER_QUERY }))
-        .send()
+        .send("fetch_viewer_id", Idempotency::Idempotent, |http| {
+            http.post(user_endpoint)
+                .bearer_auth(access_token)
+                .json(&json!({ "query": USER_QUERY }))
+        })
         .await
         .map_err(|e| {
             sentry::with_scope(
@@ -114,28 +143,30 @@ pub async fn fetch_viewer_id(
                 || sentry::capture_error(&e),
             );
             error!("Failed to fetch AniList viewer");
-            ViewerFetchError::BadGateway(
-                "Failed to fetch AniList viewer. Please try again.".to_string(),
-            )
-        })?
-        .error_for_status()
-        .map_err(|e| {
-            sentry::with_scope(
-                |scope| {
-                    configure_oauth_scope(
-                        scope,
-                        "oauth.callback.fetch_viewer_id",
-                        discord_user_fingerprint,
-                    )
-                },
-                || sentry::capture_error(&e),
-            );
-            error!("AniList viewer request failed");
-            ViewerFetchError::BadGateway(
-                "AniList viewer request failed. Please try again.".to_string(),
-            )
+            ViewerFetchError::BadGateway(MessageKey::ViewerFetchFailed)
         })?;
 
+    // Expected when a user revokes the app, so it is not reported to Sentry.
+    if viewer_response.status() == reqwest::StatusCode::UNAUTHORIZED {
+        info!("AniList rejected the access token");
+        return Err(ViewerFetchError::Unauthorized);
+    }
+
+    let viewer_response = viewer_response.error_for_status().map_err(|e| {
+        sentry::with_scope(
+            |scope| {
+                configure_oauth_scope(
+                    scope,
+                    "oauth.callback.fetch_viewer_id",
+                    discord_user_fingerprint,
+                )
+            },
+            || sentry::capture_error(&e),
+        );
+        error!("AniList viewer request failed");
+        ViewerFetchError::BadGateway(MessageKey::ViewerRequestFailed)
+    })?;
+
     let viewer_response = viewer_response
         .json::<ViewerResponse>()
         .await
@@ -151,9 +182,7 @@ pub async fn fetch_viewer_id(
                 || sentry::capture_error(&e),
             );
             error!("Failed to parse AniList viewer");
-            ViewerFetchError::BadGateway(
-                "Failed to parse AniList viewer response. Please try again.".to_string(),
-            )
+            ViewerFetchError::BadGateway(MessageKey::ViewerParseFailed)
         })?;
 
     Ok(viewer_response.data.viewer.id)
@@ -161,7 +190,7 @@ pub async fn fetch_viewer_id(
 
 #[tracing::instrument(skip(client, client_secret, code))]
 pub async fn exchange_code_for_token(
-    client: &reqwest::Client,
+    client: &AniListClient,
     token_endpoint: &str,
     client_id: &str,
     client_secret: &str,
@@ -170,15 +199,19 @@ pub async fn exchange_code_for_token(
     discord_user_fingerprint: Option<&str>,
 ) -> Result<TokenResponse, TokenExchangeError> {
     let response = client
-        .post(token_endpoint)
-        .json(&json!({
-            "grant_type": "authorization_code",
-            "client_id": client_id,
-            "client_secret": client_secret,
-            "redirect_uri": redirect_uri,
-            "code": code,
-        }))
-        .send()
+        .send(
+            "exchange_code_for_token",
+            Idempotency::NonIdempotent,
+            |http| {
+                http.post(token_endpoint).json(&json!({
+                    "grant_type": "authorization_code",
+                    "client_id": client_id,
+                    "client_secret": client_secret,
+                    "redirect_uri": redirect_uri,
+                    "code": code,
+                }))
+            },
+        )
         .await
         .map_err(|e| {
             sentry::with_scope(
@@ -192,9 +225,7 @@ pub async fn exchange_code_for_token(
                 || sentry::capture_error(&e),
             );
             error!("AniList token exchange request failed");
-            TokenExchangeError::BadGateway(
-                "AniList token exchange request failed. Please try again.".to_string(),
-            )
+            TokenExchangeError::BadGateway(MessageKey::TokenRequestFailed)
         })?;
 
     if response.status().is_success() {
@@ -210,9 +241,7 @@ pub async fn exchange_code_for_token(
                 || sentry::capture_error(&e),
             );
             error!("Failed to parse AniList token response");
-            TokenExchangeError::BadGateway(
-                "Failed to parse AniList token response. Please try again.".to_string(),
-            )
+            TokenExchangeError::BadGateway(MessageKey::TokenParseFailed)
         });
     }
 
@@ -230,12 +259,12 @@ pub async fn exchange_code_for_token(
         .unwrap_or_else(|| "unknown_error".to_string());
 
     let friendly_message = match error_payload.as_str() {
-        "access_denied" => "Authorization was denied by AniList",
-        "invalid_grant" => "Authorization code is invalid or expired",
-        "invalid_client" => "AniList OAuth client configuration is invalid",
-        "invalid_request" => "AniList token exchange request was invalid",
-        _ if status.is_server_error() => "AniList is temporarily unavailable. Please try again.",
-        _ => "AniList token exchange failed",
+        "access_denied" => MessageKey::TokenAccessDenied,
+        "invalid_grant" => MessageKey::TokenInvalidGrant,
+        "invalid_client" => MessageKey::TokenInvalidClient,
+        "invalid_request" => MessageKey::TokenInvalidRequest,
+        _ if status.is_server_error() => MessageKey::TokenUpstreamUnavailable,
+        _ => MessageKey::TokenExchangeFailed,
     };
 
     let is_upstream_failure = status.is_server_error()
@@ -273,10 +302,10 @@ pub async fn exchange_code_for_token(
             upstream_error_code
         );
 
-        return Err(TokenExchangeError::BadGateway(friendly_message.to_string()));
+        return Err(TokenExchangeError::BadGateway(friendly_message));
     }
 
-    Err(TokenExchangeError::BadRequest(friendly_message.to_string()))
+    Err(TokenExchangeError::BadRequest(friendly_message))
 }
 
 pub fn token_expires_at(expires_in_seconds: Option<i64>) -> Option<DateTime<Utc>> {
@@ -405,7 +434,7 @@ pub async fn mark_oauth_credentials_relink_required(
     reason: &str,
     user_id_hash_salt: &str,
     db: &Pool<Postgres>,
-) -> Result<(), sqlx::Error> {
+) -> Result<bool, sqlx::Error> {
     record_identifier_fingerprint(
         &tracing::Span::current(),
         "discord_user_fingerprint",
@@ -422,13 +451,306 @@ pub async fn mark_oauth_credentials_relink_required(
     .bind(reason)
     .execute(db)
     .await
+    .map(|result| result.rows_affected() > 0)
+}
+
+#[tracing::instrument(
+    skip(db, discord_user_id, user_id_hash_salt),
+    fields(discord_user_fingerprint = tracing::field::Empty)
+)]
+pub async fn delete_oauth_credentials(
+    discord_user_id: &str,
+    user_id_hash_salt: &str,
+    db: &Pool<Postgres>,
+) -> Result<bool, sqlx::Error> {
+    record_identifier_fingerprint(
+        &tracing::Span::current(),
+        "discord_user_fingerprint",
+        discord_user_id,
+        user_id_hash_salt,
+    );
+
+    sqlx::query("DELETE FROM oauth_credentials WHERE discord_user_id = $1")
+        .bind(discord_user_id)
+        .execute(db)
+        .await
+        .map(|result| result.rows_affected() > 0)
+}
+
+/// Marks the credential relink-required and audits it as `relink_required` by `actor`
+/// in one statement, so the change and its audit trail commit or fail together.
+/// Returns the updated credential, or `None` if there is no link.
+#[tracing::instrument(
+    skip(db, discord_user_id, reason, user_id_hash_salt),
+    fields(discord_user_fingerprint = tracing::field::Empty)
+)]
+pub async fn force_relink_oauth_credential(
+    discord_user_id: &str,
+    reason: &str,
+    actor: &str,
+    user_id_hash_salt: &str,
+    db: &Pool<Postgres>,
+) -> Result<Option<OAuthCredential>, sqlx::Error> {
+    record_identifier_fingerprint(
+        &tracing::Span::current(),
+        "discord_user_fingerprint",
+        discord_user_id,
+        user_id_hash_salt,
+    );
+
+    sqlx::query_as::<_, OAuthCredential>(
+        "WITH marked AS ( \
+             UPDATE oauth_credentials \
+             SET relink_required_at = NOW(), relink_reason = $2 \
+             WHERE discord_user_id = $1 \
+             RETURNING discord_user_id, anilist_id, access_token, refresh_token, \
+             token_expires_at, token_updated_at, relink_required_at, relink_reason, created_at \
+         ), audited AS ( \
+             INSERT INTO oauth_audit_events (discord_user_id, anilist_id, event, actor, reason) \
+             SELECT discord_user_id, anilist_id, $3, $4, $2 FROM marked \
+         ) \
+         SELECT * FROM marked",
+    )
+    .bind(discord_user_id)
+    .bind(reason)
+    .bind(AUDIT_EVENT_RELINK_REQUIRED)
+    .bind(actor)
+    .fetch_optional(db)
+    .await
+}
+
+/// Deletes the credential and audits it as `unlinked` by `actor` in one statement.
+/// Returns the AniList ID that was unlinked, or `None` if there was no link.
+#[tracing::instrument(
+    skip(db, discord_user_id, user_id_hash_salt),
+    fields(discord_user_fingerprint = tracing::field::Empty)
+)]
+pub async fn unlink_oauth_credential(
+    discord_user_id: &str,
+    actor: &str,
+    user_id_hash_salt: &str,
+    db: &Pool<Postgres>,
+) -> Result<Option<i64>, sqlx::Error> {
+    record_identifier_fingerprint(
+        &tracing::Span::current(),
+        "discord_user_fingerprint",
+        discord_user_id,
+        user_id_hash_salt,
+    );
+
+    sqlx::query_scalar::<_, i64>(
+        "WITH deleted AS ( \
+             DELETE FROM oauth_credentials WHERE discord_user_id = $1 \
+             RETURNING discord_user_id, anilist_id \
+         ), audited AS ( \
+             INSERT INTO oauth_audit_events (discord_user_id, anilist_id, event, actor) \
+             SELECT discord_user_id, anilist_id, $2, $3 FROM deleted \
+         ) \
+         SELECT anilist_id FROM deleted",
+    )
+    .bind(discord_user_id)
+    .bind(AUDIT_EVENT_UNLINKED)
+    .bind(actor)
+    .fetch_optional(db)
+    .await
+}
+
+#[tracing::instrument(skip(db))]
+pub async fn list_relink_required_credentials(
+    limit: i64,
+    db: &Pool<Postgres>,
+) -> Result<Vec<OAuthCredential>, sqlx::Error> {
+    sqlx::query_as::<_, OAuthCredential>(
+        "SELECT discord_user_id, anilist_id, access_token, refresh_token, \
+         token_expires_at, token_updated_at, relink_required_at, relink_reason, created_at \
+         FROM oauth_credentials WHERE relink_required_at IS NOT NULL \
+         ORDER BY relink_required_at DESC LIMIT $1",
+    )
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+#[tracing::instrument(skip(db, search))]
+pub async fn search_credentials(
+    search: &CredentialSearch,
+    db: &Pool<Postgres>,
+) -> Result<Vec<OAuthCredential>, sqlx::Error> {
+    sqlx::query_as::<_, OAuthCredential>(
+        "SELECT discord_user_id, anilist_id, access_token, refresh_token, \
+         token_expires_at, token_updated_at, relink_required_at, relink_reason, created_at \
+         FROM oauth_credentials \
+         WHERE ($1::TEXT IS NULL OR discord_user_id = $1) \
+           AND ($2::BIGINT IS NULL OR anilist_id = $2) \
+           AND ($3::BOOLEAN IS NULL OR (relink_required_at IS NOT NULL) = $3) \
+         ORDER BY created_at DESC LIMIT $4",
+    )
+    .bind(search.discord_user_id.as_deref())
+    .bind(search.anilist_id)
+    .bind(search.relink_required)
+    .bind(search.limit)
+    .fetch_all(db)
+    .await
+}
+
+/// Appends to the audit trail. `actor` is `user`, `system`, `cli` or `admin:<token name>`.
+#[tracing::instrument(
+    skip(db, discord_user_id, anilist_id, reason, user_id_hash_salt),
+    fields(discord_user_fingerprint = tracing::field::Empty)
+)]
+pub async fn record_audit_event(
+    discord_user_id: &str,
+    anilist_id: Option<i64>,
+    event: &str,
+    actor: &str,
+    reason: Option<&str>,
+    user_id_hash_salt: &str,
+    db: &Pool<Postgres>,
+) -> Result<(), sqlx::Error> {
+    record_identifier_fingerprint(
+        &tracing::Span::current(),
+        "discord_user_fingerprint",
+        discord_user_id,
+        user_id_hash_salt,
+    );
+
+    sqlx::query(
+        "INSERT INTO oauth_audit_events (discord_user_id, anilist_id, event, actor, reason) \
+         VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(discord_user_id)
+    .bind(anilist_id)
+    .bind(event)
+    .bind(actor)
+    .bind(reason)
+    .execute(db)
+    .await
     .map(|_| ())
 }
 
-fn is_anilist_id_conflict(error: &sqlx::Error) -> bool {
+#[tracing::instrument(
+    skip(db, discord_user_id, user_id_hash_salt),
+    fields(discord_user_fingerprint = tracing::field::Empty)
+)]
+pub async fn fetch_audit_events(
+    discord_user_id: &str,
+    limit: i64,
+    user_id_hash_salt: &str,
+    db: &Pool<Postgres>,
+) -> Result<Vec<AuditEvent>, sqlx::Error> {
+    record_identifier_fingerprint(
+        &tracing::Span::current(),
+        "discord_user_fingerprint",
+        discord_user_id,
+        user_id_hash_salt,
+    );
+
+    sqlx::query_as::<_, AuditEvent>(
+        "SELECT id, discord_user_id, anilist_id, event, actor, reason, created_at \
+         FROM oauth_audit_events WHERE discord_user_id = $1 \
+         ORDER BY created_at DESC, id DESC LIMIT $2",
+    )
+    .bind(discord_user_id)
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+#[tracing::instrument(
+    skip(db, discord_user_id, user_id_hash_salt),
+    fields(discord_user_fingerprint = tracing::field::Empty)
+)]
+pub async fn fetch_oauth_sessions(
+    discord_user_id: &str,
+    user_id_hash_salt: &str,
+    db: &Pool<Postgres>,
+) -> Result<Vec<OAuthSession>, sqlx::Error> {
+    record_identifier_fingerprint(
+        &tracing::Span::current(),
+        "discord_user_fingerprint",
+        discord_user_id,
+        user_id_hash_salt,
+    );
+
+    sqlx::query_as::<_, OAuthSession>(
+        "SELECT state, discord_user_id, expires_at, used_at, created_at, locale, \
+         browser_binding_hash \
+         FROM oauth_sessions WHERE discord_user_id = $1 ORDER BY created_at DESC",
+    )
+    .bind(discord_user_id)
+    .fetch_all(db)
+    .await
+}
+
+/// Records a signed context's `nonce` as used until the context expires, returning
+/// `false` if it was already used. Expired nonces are deleted first, which keeps the
+/// table small without a retention rule.
+#[tracing::instrument(skip_all)]
+pub async fn claim_context_nonce(
+    nonce: &str,
+    expires_at: DateTime<Utc>,
+    db: &Pool<Postgres>,
+) -> Result<bool, sqlx::Error> {
+    sqlx::query("DELETE FROM oauth_context_nonces WHERE expires_at <= NOW()")
+        .execute(db)
+        .await?;
+
+    sqlx::query(
+        "INSERT INTO oauth_context_nonces (nonce, expires_at) VALUES ($1, $2) \
+         ON CONFLICT (nonce) DO NOTHING",
+    )
+    .bind(nonce)
+    .bind(expires_at)
+    .execute(db)
+    .await
+    .map(|result| result.rows_affected() == 1)
+}
+
+/// Collects everything stored about `discord_user_id`, with tokens masked.
+pub async fn export_user_data(
+    discord_user_id: &str,
+    user_id_hash_salt: &str,
+    db: &Pool<Postgres>,
+) -> Result<UserDataExport, sqlx::Error> {
+    let credential = fetch_credential_by_discord_user(discord_user_id, user_id_hash_salt, db)
+        .await?
+        .as_ref()
+        .map(CredentialSummary::from);
+    let sessions = fetch_oauth_sessions(discord_user_id, user_id_hash_salt, db)
+        .await?
+        .iter()
+        .map(SessionSummary::from)
+        .collect();
+    let audit_events = fetch_audit_events(discord_user_id, i64::MAX, user_id_hash_salt, db).await?;
+    let relink_reasons = audit_events
+        .iter()
+        .filter(|event| event.event == AUDIT_EVENT_RELINK_REQUIRED)
+        .map(|event| RelinkReason {
+            reason: event.reason.clone(),
+            actor: event.actor.clone(),
+            created_at: event.created_at,
+        })
+        .collect();
+
+    Ok(UserDataExport {
+        discord_user_id: discord_user_id.to_string(),
+        generated_at: Utc::now(),
+        credential,
+        sessions,
+        audit_events,
+        relink_reasons,
+    })
+}
+
+pub(crate) fn is_anilist_id_conflict(error: &sqlx::Error) -> bool {
     match error {
         sqlx::Error::Database(database_error) => {
             database_error.constraint() == Some("uq_oauth_credentials_anilist_id")
+                // SQLite names the column instead of the constraint.
+                || (database_error.is_unique_violation()
+                    && database_error
+                        .message()
+                        .contains("oauth_credentials.anilist_id"))
         }
         _ => false,
     }
@@ -560,9 +882,196 @@ pub async fn fetch_usable_oauth_credential(
     );
     warn!("AniList credential expired and now requires relink");
 
+    if let Err(error) = record_audit_event(
+        discord_user_id,
+        Some(credential.anilist_id),
+        AUDIT_EVENT_RELINK_REQUIRED,
+        AUDIT_ACTOR_SYSTEM,
+        Some(RELINK_REASON_TOKEN_EXPIRED),
+        user_id_hash_salt,
+        db,
+    )
+    .await
+    {
+        sentry::with_scope(
+            |scope| {
+                configure_oauth_scope(
+                    scope,
+                    "oauth.credentials.record_audit_event",
+                    Some(discord_user_fingerprint.as_str()),
+                )
+            },
+            || sentry::capture_error(&error),
+        );
+        error!("Failed to record relink audit event");
+    }
+
     Err(UsableCredentialError::RelinkRequired)
 }
 
+#[tracing::instrument(
+    skip(db, discord_user_id, access_token, user_id_hash_salt),
+    fields(discord_user_fingerprint = tracing::field::Empty)
+)]
+async fn mark_probed_oauth_credential_relink_required(
+    discord_user_id: &str,
+    access_token: &str,
+    reason: &str,
+    user_id_hash_salt: &str,
+    db: &Pool<Postgres>,
+) -> Result<bool, sqlx::Error> {
+    record_identifier_fingerprint(
+        &tracing::Span::current(),
+        "discord_user_fingerprint",
+        discord_user_id,
+        user_id_hash_salt,
+    );
+
+    // Only the token that was probed is marked, so a relink that lands mid-probe survives.
+    sqlx::query(
+        "UPDATE oauth_credentials \
+         SET relink_required_at = NOW(), relink_reason = $3 \
+         WHERE discord_user_id = $1 \
+           AND access_token = $2 \
+           AND relink_required_at IS NULL",
+    )
+    .bind(discord_user_id)
+    .bind(access_token)
+    .bind(reason)
+    .execute(db)
+    .await
+    .map(|result| result.rows_affected() > 0)
+}
+
+async fn touch_oauth_credential_probe(
+    discord_user_id: &str,
+    db: &Pool<Postgres>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE oauth_credentials SET last_probed_at = NOW() WHERE discord_user_id = $1")
+        .bind(discord_user_id)
+        .execute(db)
+        .await
+        .map(|_| ())
+}
+
+/// Usable credentials that have gone the longest without a probe, never-probed first.
+pub async fn sample_oauth_credentials_for_probe(
+    limit: i64,
+    db: &Pool<Postgres>,
+) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar(
+        "SELECT discord_user_id FROM oauth_credentials \
+         WHERE relink_required_at IS NULL \
+         ORDER BY last_probed_at ASC NULLS FIRST, token_updated_at ASC \
+         LIMIT $1",
+    )
+    .bind(limit)
+    .fetch_all(db)
+    .await
+}
+
+/// Returns `(usable, relink_required)` credential counts.
+pub async fn count_oauth_credentials_by_state(
+    db: &Pool<Postgres>,
+) -> Result<(i64, i64), sqlx::Error> {
+    sqlx::query_as(
+        "SELECT \
+           COUNT(*) FILTER (WHERE relink_required_at IS NULL), \
+           COUNT(*) FILTER (WHERE relink_required_at IS NOT NULL) \
+         FROM oauth_credentials",
+    )
+    .fetch_one(db)
+    .await
+}
+
+/// Probes AniList's `Viewer` with the stored token. A `401` marks the credential
+/// relink-required with [`RELINK_REASON_TOKEN_REVOKED`], a viewer other than the
+/// linked `anilist_id` with [`RELINK_REASON_ANILIST_ID_MISMATCH`], and either is
+/// recorded in the audit trail under `actor`. Tokens already past `token_expires_at`
+/// are marked `token_expired` without calling AniList.
+#[tracing::instrument(
+    skip(client, user_endpoint, discord_user_id, user_id_hash_salt, db),
+    fields(discord_user_fingerprint = tracing::field::Empty)
+)]
+pub async fn verify_oauth_credential(
+    client: &AniListClient,
+    user_endpoint: &str,
+    discord_user_id: &str,
+    actor: &str,
+    user_id_hash_salt: &str,
+    db: &Pool<Postgres>,
+) -> Result<CredentialHealth, CredentialVerificationError> {
+    let discord_user_fingerprint = identifier_fingerprint(discord_user_id, user_id_hash_salt);
+    tracing::Span::current().record(
+        "discord_user_fingerprint",
+        tracing::field::display(&discord_user_fingerprint),
+    );
+
+    let credential = match fetch_usable_oauth_credential(discord_user_id, user_id_hash_salt, db)
+        .await
+    {
+        Ok(credential) => credential,
+        Err(UsableCredentialError::Missing) => return Err(CredentialVerificationError::Missing),
+        Err(UsableCredentialError::RelinkRequired) => return Ok(CredentialHealth::RelinkRequired),
+        Err(UsableCredentialError::Db(e)) => return Err(CredentialVerificationError::Db(e)),
+    };
+
+    let viewer = fetch_viewer_id(
+        client,
+        user_endpoint,
+        &credential.access_token,
+        Some(discord_user_fingerprint.as_str()),
+    )
+    .await;
+
+    touch_oauth_credential_probe(discord_user_id, db)
+        .await
+        .map_err(CredentialVerificationError::Db)?;
+
+    let (health, reason) = match viewer {
+        Ok(anilist_id) if anilist_id == credential.anilist_id => {
+            return Ok(CredentialHealth::Valid);
+        }
+        Ok(_) => (
+            CredentialHealth::AnilistIdMismatch,
+            RELINK_REASON_ANILIST_ID_MISMATCH,
+        ),
+        Err(ViewerFetchError::Unauthorized) => {
+            (CredentialHealth::TokenRevoked, RELINK_REASON_TOKEN_REVOKED)
+        }
+        Err(e) => return Err(CredentialVerificationError::Upstream(e)),
+    };
+
+    if !mark_probed_oauth_credential_relink_required(
+        discord_user_id,
+        &credential.access_token,
+        reason,
+        user_id_hash_salt,
+        db,
+    )
+    .await
+    .map_err(CredentialVerificationError::Db)?
+    {
+        // Relinked or already marked while AniList was being asked.
+        return Ok(CredentialHealth::RelinkRequired);
+    }
+
+    warn!("AniList credential failed verification ({reason}) and now requires relink");
+    record_audit_event(
+        discord_user_id,
+        Some(credential.anilist_id),
+        AUDIT_EVENT_RELINK_REQUIRED,
+        actor,
+        Some(reason),
+        user_id_hash_salt,
+        db,
+    )
+    .await
+    .map_err(CredentialVerificationError::Db)?;
+
+    Ok(health)
+}
+
 pub fn get_state_token() -> String {
     nanoid!(32)
 }
@@ -628,6 +1137,20 @@ pub fn verify_oauth_context(
     Ok(payload)
 }
 
+/// CDN URL for a Discord avatar hash. Returns `None` for anything that isn't a
+/// snowflake/hash pair so the consent page never embeds an arbitrary URL.
+pub fn discord_avatar_url(discord_user_id: &str, avatar_hash: &str) -> Option<String> {
+    let valid_id =
+        !discord_user_id.is_empty() && discord_user_id.chars().all(|c| c.is_ascii_digit());
+    let valid_hash = !avatar_hash.is_empty()
+        && avatar_hash
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    (valid_id && valid_hash)
+        .then(|| format!("{DISCORD_CDN}/avatars/{discord_user_id}/{avatar_hash}.png?size=128"))
+}
+
 #[tracing::instrument(
     skip(state, db, discord_user_id, user_id_hash_salt),
     fields(discord_user_fingerprint = tracing::field::Empty)
@@ -636,6 +1159,8 @@ pub async fn insert_oauth_session(
     state: &str,
     discord_user_id: &str,
     ttl_seconds: i64,
+    locale: Option<Locale>,
+    browser_binding_hash: &str,
     user_id_hash_salt: &str,
     db: &Pool<Postgres>,
 ) -> Result<(), sqlx::Error> {
@@ -647,12 +1172,14 @@ pub async fn insert_oauth_session(
     );
 
     sqlx::query(
-        "INSERT INTO oauth_sessions (state, discord_user_id, expires_at) \
-         VALUES ($1, $2, NOW() + ($3 * INTERVAL '1 second'))",
+        "INSERT INTO oauth_sessions (state, discord_user_id, expires_at, locale, browser_binding_hash) \
+         VALUES ($1, $2, NOW() + ($3 * INTERVAL '1 second'), $4, $5)",
     )
     .bind(state)
     .bind(discord_user_id)
     .bind(ttl_seconds)
+    .bind(locale.map(Locale::as_str))
+    .bind(browser_binding_hash)
     .execute(db)
     .await
     .map(|_| ())
@@ -663,21 +1190,35 @@ pub enum SessionConsumeError {
     NotFound,
     Expired,
     AlreadyUsed,
+    /// The callback was opened in a browser other than the one that started the flow.
+    /// The session is left unused so the original browser can still complete it.
+    BrowserMismatch,
     Db(sqlx::Error),
 }
 
-#[tracing::instrument(skip(state_val, db))]
+/// Only a BLAKE3 hash of the browser binding cookie is stored with the session.
+pub fn browser_binding_hash(binding: &str) -> String {
+    blake3::hash(binding.as_bytes()).to_hex().to_string()
+}
+
+/// Sessions created before browser binding existed have no hash and are accepted
+/// without a cookie.
+#[tracing::instrument(skip(state_val, browser_binding_hash, db))]
 pub async fn consume_oauth_session(
     state_val: &str,
+    browser_binding_hash: Option<&str>,
     db: &Pool<Postgres>,
 ) -> Result<OAuthSession, SessionConsumeError> {
     let session = sqlx::query_as::<_, OAuthSession>(
         "UPDATE oauth_sessions \
          SET used_at = NOW() \
          WHERE state = $1 AND used_at IS NULL AND expires_at > NOW() \
-         RETURNING state, discord_user_id, expires_at, used_at, created_at",
+           AND (browser_binding_hash IS NULL OR browser_binding_hash = $2) \
+         RETURNING state, discord_user_id, expires_at, used_at, created_at, locale, \
+           browser_binding_hash",
     )
     .bind(state_val)
+    .bind(browser_binding_hash)
     .fetch_optional(db)
     .await
     .map_err(SessionConsumeError::Db)?;
@@ -689,30 +1230,202 @@ pub async fn consume_oauth_session(
     #[derive(sqlx::FromRow)]
     struct Diag {
         used_at: Option<DateTime<Utc>>,
+        expired: bool,
     }
 
-    let diag = sqlx::query_as::<_, Diag>("SELECT used_at FROM oauth_sessions WHERE state = $1")
-        .bind(state_val)
-        .fetch_optional(db)
-        .await
-        .map_err(SessionConsumeError::Db)?;
+    let diag = sqlx::query_as::<_, Diag>(
+        "SELECT used_at, expires_at <= NOW() AS expired FROM oauth_sessions WHERE state = $1",
+    )
+    .bind(state_val)
+    .fetch_optional(db)
+    .await
+    .map_err(SessionConsumeError::Db)?;
 
     match diag {
         None => Err(SessionConsumeError::NotFound),
         Some(d) if d.used_at.is_some() => Err(SessionConsumeError::AlreadyUsed),
-        Some(_) => Err(SessionConsumeError::Expired),
+        Some(d) if d.expired => Err(SessionConsumeError::Expired),
+        Some(_) => Err(SessionConsumeError::BrowserMismatch),
     }
 }
 
+/// Deletes sessions that can no longer complete a callback. Pending sessions are
+/// only removed when `include_pending` is set.
+#[tracing::instrument(skip(db))]
+pub async fn purge_oauth_sessions(
+    include_pending: bool,
+    db: &Pool<Postgres>,
+) -> Result<u64, sqlx::Error> {
+    sqlx::query(
+        "DELETE FROM oauth_sessions \
+         WHERE $1 OR used_at IS NOT NULL OR expires_at <= NOW()",
+    )
+    .bind(include_pending)
+    .execute(db)
+    .await
+    .map(|result| result.rows_affected())
+}
+
+/// Deletes used or expired sessions created more than `older_than_days` ago.
+#[tracing::instrument(skip(db))]
+pub async fn delete_retired_oauth_sessions(
+    older_than_days: i64,
+    db: &Pool<Postgres>,
+) -> Result<u64, sqlx::Error> {
+    sqlx::query(
+        "DELETE FROM oauth_sessions \
+         WHERE created_at < NOW() - ($1 * INTERVAL '1 day') \
+           AND (used_at IS NOT NULL OR expires_at <= NOW())",
+    )
+    .bind(older_than_days)
+    .execute(db)
+    .await
+    .map(|result| result.rows_affected())
+}
+
+/// Deletes credentials that have needed a relink for more than `older_than_days`,
+/// returning the `(discord_user_id, anilist_id)` pairs removed. Each removal is
+/// audited as `unlinked` by `system` in the same statement, so the delete and its
+/// audit trail commit or fail together.
+#[tracing::instrument(skip(db))]
+pub async fn delete_stale_relink_required_credentials(
+    older_than_days: i64,
+    reason: &str,
+    db: &Pool<Postgres>,
+) -> Result<Vec<(String, i64)>, sqlx::Error> {
+    sqlx::query_as::<_, (String, i64)>(
+        "WITH deleted AS ( \
+             DELETE FROM oauth_credentials \
+             WHERE relink_required_at < NOW() - ($1 * INTERVAL '1 day') \
+             RETURNING discord_user_id, anilist_id \
+         ), audited AS ( \
+             INSERT INTO oauth_audit_events (discord_user_id, anilist_id, event, actor, reason) \
+             SELECT discord_user_id, anilist_id, $2, $3, $4 FROM deleted \
+         ) \
+         SELECT discord_user_id, anilist_id FROM deleted",
+    )
+    .bind(older_than_days)
+    .bind(AUDIT_EVENT_UNLINKED)
+    .bind(AUDIT_ACTOR_SYSTEM)
+    .bind(reason)
+    .fetch_all(db)
+    .await
+}
+
+/// Replaces `discord_user_id` with its fingerprint and clears `anilist_id` and `reason`
+/// on audit events older than `older_than_days`. Rows are only anonymized once.
+#[tracing::instrument(skip(db, user_id_hash_salt))]
+pub async fn anonymize_audit_events(
+    older_than_days: i64,
+    user_id_hash_salt: &str,
+    db: &Pool<Postgres>,
+) -> Result<u64, sqlx::Error> {
+    let discord_user_ids = sqlx::query_scalar::<_, String>(
+        "SELECT DISTINCT discord_user_id FROM oauth_audit_events \
+         WHERE anonymized_at IS NULL AND created_at < NOW() - ($1 * INTERVAL '1 day')",
+    )
+    .bind(older_than_days)
+    .fetch_all(db)
+    .await?;
+
+    let mut anonymized = 0;
+    for discord_user_id in discord_user_ids {
+        anonymized += sqlx::query(
+            "UPDATE oauth_audit_events \
+             SET discord_user_id = $2, anilist_id = NULL, reason = NULL, anonymized_at = NOW() \
+             WHERE discord_user_id = $1 AND anonymized_at IS NULL \
+               AND created_at < NOW() - ($3 * INTERVAL '1 day')",
+        )
+        .bind(&discord_user_id)
+        .bind(identifier_fingerprint(&discord_user_id, user_id_hash_salt))
+        .bind(older_than_days)
+        .execute(db)
+        .await?
+        .rows_affected();
+    }
+
+    Ok(anonymized)
+}
+
+/// Compares the migrations embedded in this build against `_sqlx_migrations`.
+pub async fn fetch_migration_status(
+    db: &Pool<Postgres>,
+) -> Result<Vec<MigrationStatus>, sqlx::Error> {
+    #[derive(sqlx::FromRow)]
+    struct AppliedMigration {
+        version: i64,
+        description: String,
+        installed_on: DateTime<Utc>,
+        success: bool,
+        checksum: Vec<u8>,
+    }
+
+    let table_exists =
+        sqlx::query_scalar::<_, bool>("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+            .fetch_one(db)
+            .await?;
+
+    let mut applied: BTreeMap<i64, AppliedMigration> = if table_exists {
+        sqlx::query_as::<_, AppliedMigration>(
+            "SELECT version, description, installed_on, success, checksum \
+             FROM _sqlx_migrations ORDER BY version",
+        )
+        .fetch_all(db)
+        .await?
+        .into_iter()
+        .map(|migration| (migration.version, migration))
+        .collect()
+    } else {
+        BTreeMap::new()
+    };
+
+    let mut statuses: Vec<MigrationStatus> = MIGRATOR
+        .iter()
+        .filter(|migration| migration.migration_type.is_up_migration())
+        .map(|migration| {
+            let (state, installed_on) = match applied.remove(&migration.version) {
+                None => (MigrationState::Pending, None),
+                Some(row) if !row.success => (MigrationState::Failed, Some(row.installed_on)),
+                Some(row) if row.checksum != *migration.checksum => {
+                    (MigrationState::ChecksumMismatch, Some(row.installed_on))
+                }
+                Some(row) => (MigrationState::Applied, Some(row.installed_on)),
+            };
+
+            MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                state,
+                installed_on,
+            }
+        })
+        .collect();
+
+    // Anything left was applied by a build that knows about migrations this one does not.
+    statuses.extend(applied.into_values().map(|row| MigrationStatus {
+        version: row.version,
+        description: row.description,
+        state: MigrationState::Unknown,
+        installed_on: Some(row.installed_on),
+    }));
+    statuses.sort_by_key(|status| status.version);
+
+    Ok(statuses)
+}
+
 #[cfg(test)]
 mod tests {
     use super::{
         OAuthContextError, SessionConsumeError, UpsertOAuthCredentialsError, UsableCredentialError,
-        consume_oauth_session, fetch_credential_by_anilist_id, fetch_credential_by_discord_user,
-        fetch_usable_oauth_credential, insert_oauth_session,
+        consume_oauth_session, delete_oauth_credentials, discord_avatar_url, fetch_audit_events,
+        fetch_credential_by_anilist_id, fetch_credential_by_discord_user, fetch_migration_status,
+        fetch_usable_oauth_credential, insert_oauth_session, list_relink_required_credentials,
         mark_expired_oauth_credential_relink_required, mark_oauth_credentials_relink_required,
-        token_expires_at, upsert_oauth_credentials, verify_oauth_context,
+        purge_oauth_sessions, record_audit_event, search_credentials, token_expires_at,
+        upsert_oauth_credentials, verify_oauth_context,
     };
+    use crate::utils::structs::CredentialSearch;
+    use crate::utils::structs::MigrationState;
     use base64::{Engine as _, engine::general_purpose::URL_SAFE_NO_PAD};
     use chrono::{Duration, Utc};
     use hmac::{Hmac, KeyInit, Mac};
@@ -733,6 +1446,17 @@ mod tests {
         format!("{payload_segment}.{signature_segment}")
     }
 
+    #[test]
+    fn discord_avatar_url_rejects_unexpected_input() {
+        assert_eq!(
+            discord_avatar_url("123456789", "a_1b2c3d").as_deref(),
+            Some("https://cdn.discordapp.com/avatars/123456789/a_1b2c3d.png?size=128")
+        );
+        assert!(discord_avatar_url("123456789", "../../evil").is_none());
+        assert!(discord_avatar_url("123?x=1", "abc").is_none());
+        assert!(discord_avatar_url("123456789", "").is_none());
+    }
+
     #[test]
     fn verify_oauth_context_accepts_valid_payload() {
         let now = Utc::now().timestamp();
@@ -956,6 +1680,14 @@ mod tests {
         assert!(credential.relink_required_at.is_some());
         assert_eq!(credential.relink_reason.as_deref(), Some("token_expired"));
 
+        let events = fetch_audit_events("expired_user", 10, TEST_USERID_HASH_SALT, &pool)
+            .await
+            .expect("audit events should load");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, "relink_required");
+        assert_eq!(events[0].actor, "system");
+        assert_eq!(events[0].reason.as_deref(), Some("token_expired"));
+
         pool.close().await;
     }
 
@@ -1228,11 +1960,19 @@ mod tests {
 
     #[sqlx::test(migrations = "./migrations")]
     async fn consume_session_succeeds_for_valid_state(pool: Pool<Postgres>) {
-        insert_oauth_session("state_abc", "123456789", 600, TEST_USERID_HASH_SALT, &pool)
-            .await
-            .expect("insert should succeed");
+        insert_oauth_session(
+            "state_abc",
+            "123456789",
+            600,
+            None,
+            "binding-hash",
+            TEST_USERID_HASH_SALT,
+            &pool,
+        )
+        .await
+        .expect("insert should succeed");
 
-        let session = consume_oauth_session("state_abc", &pool)
+        let session = consume_oauth_session("state_abc", Some("binding-hash"), &pool)
             .await
             .expect("consume should succeed");
 
@@ -1244,7 +1984,7 @@ mod tests {
 
     #[sqlx::test(migrations = "./migrations")]
     async fn consume_session_fails_for_missing_state(pool: Pool<Postgres>) {
-        let err = consume_oauth_session("no_such_state", &pool)
+        let err = consume_oauth_session("no_such_state", None, &pool)
             .await
             .expect_err("consume should fail");
 
@@ -1255,15 +1995,23 @@ mod tests {
 
     #[sqlx::test(migrations = "./migrations")]
     async fn consume_session_fails_on_replay(pool: Pool<Postgres>) {
-        insert_oauth_session("replayable", "111", 600, TEST_USERID_HASH_SALT, &pool)
-            .await
-            .expect("insert should succeed");
+        insert_oauth_session(
+            "replayable",
+            "111",
+            600,
+            None,
+            "binding-hash",
+            TEST_USERID_HASH_SALT,
+            &pool,
+        )
+        .await
+        .expect("insert should succeed");
 
-        consume_oauth_session("replayable", &pool)
+        consume_oauth_session("replayable", Some("binding-hash"), &pool)
             .await
             .expect("first consume should succeed");
 
-        let err = consume_oauth_session("replayable", &pool)
+        let err = consume_oauth_session("replayable", Some("binding-hash"), &pool)
             .await
             .expect_err("replay should fail");
 
@@ -1272,6 +2020,34 @@ mod tests {
         pool.close().await;
     }
 
+    #[sqlx::test(migrations = "./migrations")]
+    async fn consume_session_requires_matching_browser_binding(pool: Pool<Postgres>) {
+        insert_oauth_session(
+            "bound",
+            "111",
+            600,
+            None,
+            "binding-hash",
+            TEST_USERID_HASH_SALT,
+            &pool,
+        )
+        .await
+        .expect("insert should succeed");
+
+        for presented in [None, Some("other-hash")] {
+            let err = consume_oauth_session("bound", presented, &pool)
+                .await
+                .expect_err("mismatched binding should fail");
+            assert!(matches!(err, SessionConsumeError::BrowserMismatch));
+        }
+
+        consume_oauth_session("bound", Some("binding-hash"), &pool)
+            .await
+            .expect("the original browser should still complete the flow");
+
+        pool.close().await;
+    }
+
     #[sqlx::test(migrations = "./migrations")]
     async fn consume_session_fails_for_expired_state(pool: Pool<Postgres>) {
         sqlx::query(
@@ -1284,7 +2060,7 @@ mod tests {
         .await
         .expect("direct insert should succeed");
 
-        let err = consume_oauth_session("expired_state", &pool)
+        let err = consume_oauth_session("expired_state", None, &pool)
             .await
             .expect_err("expired session should fail");
 
@@ -1293,6 +2069,256 @@ mod tests {
         pool.close().await;
     }
 
+    #[sqlx::test(migrations = "./migrations")]
+    async fn mark_relink_required_reports_missing_credentials(pool: Pool<Postgres>) {
+        let marked = mark_oauth_credentials_relink_required(
+            "missing_user",
+            "admin_forced",
+            TEST_USERID_HASH_SALT,
+            &pool,
+        )
+        .await
+        .expect("mark relink required should not error");
+
+        assert!(!marked);
+
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn delete_oauth_credentials_removes_link(pool: Pool<Postgres>) {
+        upsert_oauth_credentials(
+            "unlink_user",
+            4_242,
+            "tok",
+            None,
+            None,
+            TEST_USERID_HASH_SALT,
+            &pool,
+        )
+        .await
+        .expect("upsert should succeed");
+
+        let deleted = delete_oauth_credentials("unlink_user", TEST_USERID_HASH_SALT, &pool)
+            .await
+            .expect("delete should succeed");
+        assert!(deleted);
+
+        let credential =
+            fetch_credential_by_discord_user("unlink_user", TEST_USERID_HASH_SALT, &pool)
+                .await
+                .expect("fetch should not error");
+        assert!(credential.is_none());
+
+        let deleted_again = delete_oauth_credentials("unlink_user", TEST_USERID_HASH_SALT, &pool)
+            .await
+            .expect("delete should succeed");
+        assert!(!deleted_again);
+
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn list_relink_required_credentials_returns_only_flagged(pool: Pool<Postgres>) {
+        for (discord_user_id, anilist_id) in [("healthy_user", 1), ("flagged_user", 2)] {
+            upsert_oauth_credentials(
+                discord_user_id,
+                anilist_id,
+                "tok",
+                None,
+                None,
+                TEST_USERID_HASH_SALT,
+                &pool,
+            )
+            .await
+            .expect("upsert should succeed");
+        }
+
+        mark_oauth_credentials_relink_required(
+            "flagged_user",
+            "admin_forced",
+            TEST_USERID_HASH_SALT,
+            &pool,
+        )
+        .await
+        .expect("mark relink required should succeed");
+
+        let credentials = list_relink_required_credentials(10, &pool)
+            .await
+            .expect("list should succeed");
+
+        assert_eq!(credentials.len(), 1);
+        assert_eq!(credentials[0].discord_user_id, "flagged_user");
+        assert_eq!(
+            credentials[0].relink_reason.as_deref(),
+            Some("admin_forced")
+        );
+
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn purge_oauth_sessions_keeps_pending_sessions_unless_requested(pool: Pool<Postgres>) {
+        insert_oauth_session(
+            "pending",
+            "111",
+            600,
+            None,
+            "b1",
+            TEST_USERID_HASH_SALT,
+            &pool,
+        )
+        .await
+        .expect("insert should succeed");
+        insert_oauth_session("used", "222", 600, None, "b2", TEST_USERID_HASH_SALT, &pool)
+            .await
+            .expect("insert should succeed");
+        consume_oauth_session("used", Some("b2"), &pool)
+            .await
+            .expect("consume should succeed");
+        sqlx::query(
+            "INSERT INTO oauth_sessions (state, discord_user_id, expires_at) \
+             VALUES ('expired', '333', NOW() - INTERVAL '1 minute')",
+        )
+        .execute(&pool)
+        .await
+        .expect("direct insert should succeed");
+
+        let deleted = purge_oauth_sessions(false, &pool)
+            .await
+            .expect("purge should succeed");
+        assert_eq!(deleted, 2);
+
+        let deleted = purge_oauth_sessions(true, &pool)
+            .await
+            .expect("purge should succeed");
+        assert_eq!(deleted, 1);
+
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn search_credentials_combines_filters(pool: Pool<Postgres>) {
+        for (discord_user_id, anilist_id) in [("search_a", 10), ("search_b", 20)] {
+            upsert_oauth_credentials(
+                discord_user_id,
+                anilist_id,
+                "tok",
+                None,
+                None,
+                TEST_USERID_HASH_SALT,
+                &pool,
+            )
+            .await
+            .expect("upsert should succeed");
+        }
+        mark_oauth_credentials_relink_required(
+            "search_b",
+            "admin_forced",
+            TEST_USERID_HASH_SALT,
+            &pool,
+        )
+        .await
+        .expect("mark relink required should succeed");
+
+        let all = search_credentials(
+            &CredentialSearch {
+                limit: 10,
+                ..Default::default()
+            },
+            &pool,
+        )
+        .await
+        .expect("search should succeed");
+        assert_eq!(all.len(), 2);
+
+        let by_anilist = search_credentials(
+            &CredentialSearch {
+                anilist_id: Some(10),
+                limit: 10,
+                ..Default::default()
+            },
+            &pool,
+        )
+        .await
+        .expect("search should succeed");
+        assert_eq!(by_anilist.len(), 1);
+        assert_eq!(by_anilist[0].discord_user_id, "search_a");
+
+        let healthy = search_credentials(
+            &CredentialSearch {
+                relink_required: Some(false),
+                limit: 10,
+                ..Default::default()
+            },
+            &pool,
+        )
+        .await
+        .expect("search should succeed");
+        assert_eq!(healthy.len(), 1);
+        assert_eq!(healthy[0].discord_user_id, "search_a");
+
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn audit_events_are_returned_newest_first(pool: Pool<Postgres>) {
+        for event in ["linked", "relink_required", "unlinked"] {
+            record_audit_event(
+                "audited_user",
+                Some(5),
+                event,
+                "cli",
+                None,
+                TEST_USERID_HASH_SALT,
+                &pool,
+            )
+            .await
+            .expect("audit insert should succeed");
+        }
+
+        let events = fetch_audit_events("audited_user", 2, TEST_USERID_HASH_SALT, &pool)
+            .await
+            .expect("audit events should load");
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event, "unlinked");
+        assert_eq!(events[1].event, "relink_required");
+
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn fetch_migration_status_reports_applied_migrations(pool: Pool<Postgres>) {
+        let statuses = fetch_migration_status(&pool)
+            .await
+            .expect("migration status should load");
+
+        assert!(!statuses.is_empty());
+        assert!(
+            statuses
+                .iter()
+                .all(|status| status.state == MigrationState::Applied)
+        );
+
+        sqlx::query("DELETE FROM _sqlx_migrations WHERE version = 20260401000003")
+            .execute(&pool)
+            .await
+            .expect("direct delete should succeed");
+
+        let statuses = fetch_migration_status(&pool)
+            .await
+            .expect("migration status should load");
+        let pending = statuses
+            .iter()
+            .find(|status| status.version == 20260401000003)
+            .expect("deleted migration should still be listed");
+        assert_eq!(pending.state, MigrationState::Pending);
+        assert!(pending.installed_on.is_none());
+
+        pool.close().await;
+    }
+
     #[test]
     fn token_expiry_defaults_to_one_year_when_missing() {
         let default_expiry =
@@ -1,11 +1,38 @@
 use rocket::{
-    http::Status,
+    http::{Cookie, Status},
     request::{FromRequest, Outcome, Request},
 };
 
-use super::structs::{MyState, StateToken, StateTokenError};
-use crate::utils::functions::{SessionConsumeError, consume_oauth_session};
-use crate::utils::observability::configure_oauth_scope;
+use super::structs::{
+    AdminAuthError, AdminRead, AdminScope, AdminWrite, ClientRateLimit, CspNonce, MyState,
+    PageContext, RequestId, StateToken, StateTokenError,
+};
+use crate::utils::consts::{BINDING_COOKIE_PATH, BROWSER_BINDING_COOKIE};
+use crate::utils::functions::{SessionConsumeError, browser_binding_hash, get_state_token};
+use crate::utils::observability::{REQUEST_ID_TAG, configure_oauth_scope};
+use crate::utils::{i18n::Locale, pages::Pages};
+
+use std::{net::IpAddr, sync::Once};
+
+static PROXY_PEER_WARNING: Once = Once::new();
+
+/// Loopback, private and carrier-grade NAT addresses, which is where a reverse
+/// proxy's connections come from.
+fn is_internal_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            ip.is_loopback() || ip.is_private() || (first == 100 && (second & 0xC0) == 64)
+        }
+        IpAddr::V6(ip) => {
+            ip.is_loopback()
+                || ip.is_unique_local()
+                || ip
+                    .to_ipv4_mapped()
+                    .is_some_and(|ip| is_internal_address(IpAddr::V4(ip)))
+        }
+    }
+}
 
 #[rocket::async_trait]
 impl<'r> FromRequest<'r> for StateToken {
@@ -21,16 +48,35 @@ impl<'r> FromRequest<'r> for StateToken {
             Some(Ok(s)) => s,
         };
 
-        let pool = match req.rocket().state::<MyState>() {
-            Some(s) => &s.pool,
+        let sessions = match req.rocket().state::<MyState>() {
+            Some(s) => &s.storage.sessions,
             None => {
                 error!("MyState not managed -- cannot validate OAuth session");
                 return Outcome::Error((Status::InternalServerError, StateTokenError::Internal));
             }
         };
 
-        match consume_oauth_session(state_val, pool).await {
-            Ok(session) => Outcome::Success(StateToken(session.discord_user_id)),
+        // Guards run before the handler span exists, so tag the request ID directly.
+        let request_id = RequestId::for_request(req);
+
+        let binding_hash = req
+            .cookies()
+            .get_private(BROWSER_BINDING_COOKIE)
+            .map(|cookie| browser_binding_hash(cookie.value()));
+
+        match sessions
+            .consume_oauth_session(state_val, binding_hash.as_deref())
+            .await
+        {
+            Ok(session) => {
+                req.cookies().remove_private(
+                    Cookie::build(BROWSER_BINDING_COOKIE).path(BINDING_COOKIE_PATH),
+                );
+                Outcome::Success(StateToken {
+                    locale: session.locale.as_deref().and_then(Locale::from_tag),
+                    discord_user_id: session.discord_user_id,
+                })
+            }
             Err(SessionConsumeError::NotFound) => {
                 info!("State validation failed: session not found");
                 Outcome::Error((Status::BadRequest, StateTokenError::Invalid))
@@ -44,6 +90,7 @@ impl<'r> FromRequest<'r> for StateToken {
                 sentry::with_scope(
                     |scope| {
                         configure_oauth_scope(scope, "oauth.state.consume_session", None);
+                        scope.set_tag(REQUEST_ID_TAG, &request_id.0);
                     },
                     || {
                         sentry::capture_message(
@@ -54,10 +101,27 @@ impl<'r> FromRequest<'r> for StateToken {
                 );
                 Outcome::Error((Status::BadRequest, StateTokenError::Replayed))
             }
+            Err(SessionConsumeError::BrowserMismatch) => {
+                info!("State validation failed: callback opened in a different browser");
+                sentry::with_scope(
+                    |scope| {
+                        configure_oauth_scope(scope, "oauth.state.consume_session", None);
+                        scope.set_tag(REQUEST_ID_TAG, &request_id.0);
+                    },
+                    || {
+                        sentry::capture_message(
+                            "OAuth callback browser binding mismatch",
+                            sentry::Level::Warning,
+                        )
+                    },
+                );
+                Outcome::Error((Status::Forbidden, StateTokenError::BrowserMismatch))
+            }
             Err(SessionConsumeError::Db(e)) => {
                 sentry::with_scope(
                     |scope| {
                         configure_oauth_scope(scope, "oauth.state.consume_session", None);
+                        scope.set_tag(REQUEST_ID_TAG, &request_id.0);
                     },
                     || sentry::capture_error(&e),
                 );
@@ -67,3 +131,149 @@ impl<'r> FromRequest<'r> for StateToken {
         }
     }
 }
+
+impl CspNonce {
+    /// Returns the nonce cached for this request, so pages and the CSP header agree.
+    pub fn for_request(req: &Request<'_>) -> Self {
+        req.local_cache(|| CspNonce(get_state_token())).clone()
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for CspNonce {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(CspNonce::for_request(req))
+    }
+}
+
+impl RequestId {
+    pub fn for_request(req: &Request<'_>) -> Self {
+        req.local_cache(|| RequestId(get_state_token())).clone()
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestId {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(RequestId::for_request(req))
+    }
+}
+
+impl PageContext {
+    /// Falls back to the embedded templates when `MyState` is not managed (e.g. in tests).
+    pub fn for_request(req: &Request<'_>) -> Self {
+        let pages = match req.rocket().state::<MyState>() {
+            Some(state) => state.pages.clone(),
+            None => Pages::embedded().clone(),
+        };
+
+        PageContext {
+            pages,
+            nonce: CspNonce::for_request(req),
+            request_id: RequestId::for_request(req),
+            locale: Locale::for_request(req),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for PageContext {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(PageContext::for_request(req))
+    }
+}
+
+/// Keys on `Request::client_ip`, which is the TCP peer unless `CLIENT_IP_HEADER` names
+/// a trusted proxy header. Requests without any address share a single bucket.
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ClientRateLimit {
+    type Error = ();
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let Some(state) = req.rocket().state::<MyState>() else {
+            error!("MyState not managed -- cannot apply rate limits");
+            return Outcome::Error((Status::InternalServerError, ()));
+        };
+
+        let client_ip = req.client_ip();
+        if req.rocket().config().ip_header.is_none() && client_ip.is_some_and(is_internal_address) {
+            PROXY_PEER_WARNING.call_once(|| {
+                warn!(
+                    "Requests arrive from an internal address but CLIENT_IP_HEADER is unset; \
+                     behind a reverse proxy every client shares one per-IP rate limit bucket"
+                );
+            });
+        }
+        let key = client_ip.map_or_else(|| "unknown".to_string(), |ip| ip.to_string());
+
+        if state.rate_limits.per_ip.check(&key) {
+            Outcome::Success(ClientRateLimit)
+        } else {
+            info!("Request rejected: per-IP rate limit exceeded");
+            Outcome::Error((Status::TooManyRequests, ()))
+        }
+    }
+}
+
+/// Resolves the `Authorization: Bearer` header to a configured admin token name.
+/// Unknown tokens are 401; known tokens without `scope` are 403.
+fn authenticate_admin(req: &Request<'_>, scope: AdminScope) -> Outcome<String, AdminAuthError> {
+    let Some(state) = req.rocket().state::<MyState>() else {
+        error!("MyState not managed -- cannot validate admin token");
+        return Outcome::Error((Status::InternalServerError, AdminAuthError::Internal));
+    };
+
+    let Some(presented) = req
+        .headers()
+        .get_one("Authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+    else {
+        return Outcome::Error((Status::Unauthorized, AdminAuthError::Missing));
+    };
+
+    let settings = state.settings.current();
+    let Some(token) = settings
+        .admin_tokens
+        .iter()
+        .find(|token| token.matches(presented))
+    else {
+        info!("Admin request rejected: unknown bearer token");
+        return Outcome::Error((Status::Unauthorized, AdminAuthError::Invalid));
+    };
+
+    if !token.scopes.contains(&scope) {
+        info!(
+            "Admin request rejected: token {} lacks {scope:?} scope",
+            token.name
+        );
+        return Outcome::Error((Status::Forbidden, AdminAuthError::InsufficientScope));
+    }
+
+    Outcome::Success(token.name.clone())
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AdminRead {
+    type Error = AdminAuthError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        authenticate_admin(req, AdminScope::Read).map(AdminRead)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for AdminWrite {
+    type Error = AdminAuthError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        authenticate_admin(req, AdminScope::Write).map(AdminWrite)
+    }
+}
@@ -0,0 +1,85 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Grace added on top of two intervals before a job counts as stale, so a slow run
+/// does not flap readiness.
+const STALE_GRACE: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy)]
+struct Heartbeat {
+    interval: Duration,
+    last_beat: Option<Instant>,
+    registered_at: Instant,
+}
+
+/// Last-run times of the background jobs, read by `/readyz`. Clones share state.
+#[derive(Debug, Clone, Default)]
+pub struct JobHeartbeats {
+    jobs: Arc<Mutex<BTreeMap<&'static str, Heartbeat>>>,
+}
+
+impl JobHeartbeats {
+    /// Starts tracking `job`, which is expected to beat every `interval`.
+    pub fn register(&self, job: &'static str, interval: Duration) {
+        self.jobs
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .insert(
+                job,
+                Heartbeat {
+                    interval,
+                    last_beat: None,
+                    registered_at: Instant::now(),
+                },
+            );
+    }
+
+    pub fn beat(&self, job: &'static str) {
+        if let Some(heartbeat) = self
+            .jobs
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get_mut(job)
+        {
+            heartbeat.last_beat = Some(Instant::now());
+        }
+    }
+
+    /// Seconds since each job's last beat (`None` if it has not run yet) and whether
+    /// that is longer than expected.
+    pub fn snapshot(&self) -> Vec<(&'static str, Option<u64>, bool)> {
+        self.jobs
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .iter()
+            .map(|(job, heartbeat)| {
+                let since = heartbeat.last_beat.unwrap_or(heartbeat.registered_at);
+                let stale = since.elapsed() > heartbeat.interval * 2 + STALE_GRACE;
+                let age = heartbeat.last_beat.map(|beat| beat.elapsed().as_secs());
+                (*job, age, stale)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JobHeartbeats;
+    use std::time::Duration;
+
+    #[test]
+    fn reports_registered_jobs_only() {
+        let heartbeats = JobHeartbeats::default();
+        heartbeats.beat("unregistered");
+        heartbeats.register("retention", Duration::from_secs(3600));
+
+        let snapshot = heartbeats.snapshot();
+        assert_eq!(snapshot, vec![("retention", None, false)]);
+
+        heartbeats.clone().beat("retention");
+        assert_eq!(heartbeats.snapshot(), vec![("retention", Some(0), false)]);
+    }
+}
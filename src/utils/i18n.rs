@@ -0,0 +1,483 @@
+use rocket::request::{FromRequest, Outcome, Request};
+
+/// Languages with a full message catalog. English is the fallback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    Ja,
+    Es,
+    Pt,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 4] = [Locale::En, Locale::Ja, Locale::Es, Locale::Pt];
+
+    /// Maps a BCP 47 / Discord locale tag (`ja`, `es-419`, `pt-BR`) by its primary subtag.
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        let primary = tag.trim().split(['-', '_']).next()?.to_ascii_lowercase();
+
+        match primary.as_str() {
+            "en" => Some(Locale::En),
+            "ja" => Some(Locale::Ja),
+            "es" => Some(Locale::Es),
+            "pt" => Some(Locale::Pt),
+            _ => None,
+        }
+    }
+
+    /// Picks the highest-weighted supported language from an `Accept-Language` header.
+    pub fn negotiate(accept_language: &str) -> Option<Self> {
+        let mut candidates: Vec<(f32, Locale)> = accept_language
+            .split(',')
+            .filter_map(|entry| {
+                let mut parts = entry.split(';');
+                let locale = Locale::from_tag(parts.next()?)?;
+                let weight = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())?;
+
+                (weight > 0.0).then_some((weight, locale))
+            })
+            .collect();
+
+        // Stable sort keeps header order for equal weights.
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0));
+        candidates.first().map(|(_, locale)| *locale)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Ja => "ja",
+            Locale::Es => "es",
+            Locale::Pt => "pt",
+        }
+    }
+
+    const fn index(self) -> usize {
+        match self {
+            Locale::En => 0,
+            Locale::Ja => 1,
+            Locale::Es => 2,
+            Locale::Pt => 3,
+        }
+    }
+
+    /// Negotiated from `Accept-Language`, defaulting to English.
+    pub fn for_request(req: &Request<'_>) -> Self {
+        req.headers()
+            .get_one("Accept-Language")
+            .and_then(Locale::negotiate)
+            .unwrap_or_default()
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Locale {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(Locale::for_request(req))
+    }
+}
+
+/// Every user-facing string. Translations are listed in [`Locale::ALL`] order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKey {
+    LinkSucceeded,
+    AuthorizationDenied,
+    AuthorizationFailed,
+    AuthorizationCodeMissing,
+    AlreadyLinked,
+    SaveCredentialsFailed,
+    StateMissing,
+    StateInvalid,
+    StateExpired,
+    StateReplayed,
+    StateBrowserMismatch,
+    StateInternal,
+    TokenAccessDenied,
+    TokenInvalidGrant,
+    TokenInvalidClient,
+    TokenInvalidRequest,
+    TokenUpstreamUnavailable,
+    TokenExchangeFailed,
+    TokenRequestFailed,
+    TokenParseFailed,
+    ViewerFetchFailed,
+    ViewerRequestFailed,
+    ViewerParseFailed,
+    RelinkRequired,
+    ContextInvalid,
+    StartFailed,
+    SessionCreateFailed,
+    UpstreamDegraded,
+    ErrorBadRequest,
+    ErrorForbidden,
+    ErrorUnprocessable,
+    ErrorTooManyRequests,
+    ErrorInternal,
+    ErrorUnavailable,
+    ErrorGeneric,
+    PageTitleConnected,
+    PageTitleError,
+    PageHeadingConnected,
+    PageHeadingError,
+    PageHintConnected,
+    PageHintError,
+    PageReferenceLabel,
+    NotFoundHeading,
+    NotFoundMessage,
+    ConsentTitle,
+    ConsentHeading,
+    ConsentIntro,
+    ConsentPermissions,
+    ConsentPrivacy,
+    ConsentContinue,
+    ConsentWrongAccount,
+}
+
+impl MessageKey {
+    pub const fn text(self, locale: Locale) -> &'static str {
+        self.translations()[locale.index()]
+    }
+
+    const fn translations(self) -> [&'static str; 4] {
+        match self {
+            MessageKey::LinkSucceeded => [
+                "AniList account connected successfully.",
+                "AniList アカウントの連携が完了しました。",
+                "La cuenta de AniList se conectó correctamente.",
+                "Conta do AniList conectada com sucesso.",
+            ],
+            MessageKey::AuthorizationDenied => [
+                "Authorization was denied on AniList. Please try again.",
+                "AniList で認証が拒否されました。もう一度お試しください。",
+                "Se denegó la autorización en AniList. Inténtalo de nuevo.",
+                "A autorização foi negada no AniList. Tente novamente.",
+            ],
+            MessageKey::AuthorizationFailed => [
+                "AniList authorization failed. Please try again.",
+                "AniList の認証に失敗しました。もう一度お試しください。",
+                "La autorización de AniList falló. Inténtalo de nuevo.",
+                "A autorização do AniList falhou. Tente novamente.",
+            ],
+            MessageKey::AuthorizationCodeMissing => [
+                "Authorization code is missing from the callback.",
+                "コールバックに認証コードが含まれていません。",
+                "Falta el código de autorización en la respuesta de AniList.",
+                "O código de autorização não foi recebido do AniList.",
+            ],
+            MessageKey::AlreadyLinked => [
+                "This AniList account is already linked to another Discord user.",
+                "この AniList アカウントは既に別の Discord ユーザーと連携されています。",
+                "Esta cuenta de AniList ya está vinculada a otro usuario de Discord.",
+                "Esta conta do AniList já está vinculada a outro usuário do Discord.",
+            ],
+            MessageKey::SaveCredentialsFailed => [
+                "Failed to save AniList credentials. Please retry.",
+                "AniList の認証情報を保存できませんでした。もう一度お試しください。",
+                "No se pudieron guardar las credenciales de AniList. Inténtalo de nuevo.",
+                "Não foi possível salvar as credenciais do AniList. Tente novamente.",
+            ],
+            MessageKey::StateMissing => [
+                "State parameter is missing from the callback.",
+                "コールバックに state パラメーターが含まれていません。",
+                "Falta el parámetro state en la respuesta de AniList.",
+                "O parâmetro state não foi recebido do AniList.",
+            ],
+            MessageKey::StateInvalid => [
+                "State parameter is invalid. Please restart the AniList login flow.",
+                "state パラメーターが無効です。AniList へのログインを最初からやり直してください。",
+                "El parámetro state no es válido. Vuelve a iniciar sesión en AniList desde el principio.",
+                "O parâmetro state é inválido. Reinicie o login no AniList.",
+            ],
+            MessageKey::StateExpired => [
+                "State parameter has expired. Please restart the AniList login flow.",
+                "state パラメーターの有効期限が切れました。AniList へのログインを最初からやり直してください。",
+                "El parámetro state ha caducado. Vuelve a iniciar sesión en AniList desde el principio.",
+                "O parâmetro state expirou. Reinicie o login no AniList.",
+            ],
+            MessageKey::StateReplayed => [
+                "This login link has already been used. Please restart the AniList login flow.",
+                "このログインリンクは既に使用されています。AniList へのログインを最初からやり直してください。",
+                "Este enlace de inicio de sesión ya se utilizó. Vuelve a iniciar sesión en AniList desde el principio.",
+                "Este link de login já foi usado. Reinicie o login no AniList.",
+            ],
+            MessageKey::StateBrowserMismatch => [
+                "This login was started in a different browser. Finish linking in the browser where you clicked the link from Discord, or restart the AniList login flow.",
+                "このログインは別のブラウザーで開始されました。Discord のリンクを開いたブラウザーで連携を完了するか、AniList へのログインを最初からやり直してください。",
+                "Este inicio de sesión se empezó en otro navegador. Termina la vinculación en el navegador donde abriste el enlace de Discord o vuelve a iniciar sesión en AniList.",
+                "Este login foi iniciado em outro navegador. Conclua a vinculação no navegador em que você abriu o link do Discord ou reinicie o login no AniList.",
+            ],
+            MessageKey::StateInternal => [
+                "Failed to validate the AniList login state. Please retry.",
+                "AniList のログイン状態を確認できませんでした。もう一度お試しください。",
+                "No se pudo validar el estado del inicio de sesión en AniList. Inténtalo de nuevo.",
+                "Não foi possível validar o estado do login no AniList. Tente novamente.",
+            ],
+            MessageKey::TokenAccessDenied => [
+                "Authorization was denied by AniList",
+                "AniList により認証が拒否されました",
+                "AniList denegó la autorización",
+                "O AniList negou a autorização",
+            ],
+            MessageKey::TokenInvalidGrant => [
+                "Authorization code is invalid or expired",
+                "認証コードが無効か、有効期限が切れています",
+                "El código de autorización no es válido o ha caducado",
+                "O código de autorização é inválido ou expirou",
+            ],
+            MessageKey::TokenInvalidClient => [
+                "AniList OAuth client configuration is invalid",
+                "AniList OAuth クライアントの設定が無効です",
+                "La configuración del cliente OAuth de AniList no es válida",
+                "A configuração do cliente OAuth do AniList é inválida",
+            ],
+            MessageKey::TokenInvalidRequest => [
+                "AniList token exchange request was invalid",
+                "AniList へのトークン交換リクエストが無効でした",
+                "La solicitud de intercambio de token de AniList no era válida",
+                "A solicitação de troca de token do AniList era inválida",
+            ],
+            MessageKey::TokenUpstreamUnavailable => [
+                "AniList is temporarily unavailable. Please try again.",
+                "AniList が一時的に利用できません。もう一度お試しください。",
+                "AniList no está disponible temporalmente. Inténtalo de nuevo.",
+                "O AniList está temporariamente indisponível. Tente novamente.",
+            ],
+            MessageKey::TokenExchangeFailed => [
+                "AniList token exchange failed",
+                "AniList のトークン交換に失敗しました",
+                "Falló el intercambio de token con AniList",
+                "A troca de token com o AniList falhou",
+            ],
+            MessageKey::TokenRequestFailed => [
+                "AniList token exchange request failed. Please try again.",
+                "AniList へのトークン交換リクエストに失敗しました。もう一度お試しください。",
+                "La solicitud de intercambio de token con AniList falló. Inténtalo de nuevo.",
+                "A solicitação de troca de token com o AniList falhou. Tente novamente.",
+            ],
+            MessageKey::TokenParseFailed => [
+                "Failed to parse AniList token response. Please try again.",
+                "AniList のトークン応答を解析できませんでした。もう一度お試しください。",
+                "No se pudo procesar la respuesta de token de AniList. Inténtalo de nuevo.",
+                "Não foi possível processar a resposta de token do AniList. Tente novamente.",
+            ],
+            MessageKey::ViewerFetchFailed => [
+                "Failed to fetch AniList viewer. Please try again.",
+                "AniList のユーザー情報を取得できませんでした。もう一度お試しください。",
+                "No se pudo obtener el usuario de AniList. Inténtalo de nuevo.",
+                "Não foi possível obter o usuário do AniList. Tente novamente.",
+            ],
+            MessageKey::ViewerRequestFailed => [
+                "AniList viewer request failed. Please try again.",
+                "AniList のユーザー情報リクエストに失敗しました。もう一度お試しください。",
+                "La solicitud del usuario de AniList falló. Inténtalo de nuevo.",
+                "A solicitação do usuário do AniList falhou. Tente novamente.",
+            ],
+            MessageKey::ViewerParseFailed => [
+                "Failed to parse AniList viewer response. Please try again.",
+                "AniList のユーザー情報を解析できませんでした。もう一度お試しください。",
+                "No se pudo procesar la respuesta del usuario de AniList. Inténtalo de nuevo.",
+                "Não foi possível processar a resposta do usuário do AniList. Tente novamente.",
+            ],
+            MessageKey::RelinkRequired => [
+                "Your AniList link has expired or needs to be reconnected. Please run `/register` again in Discord.",
+                "AniList との連携の有効期限が切れたか、再連携が必要です。Discord で `/register` をもう一度実行してください。",
+                "Tu vínculo con AniList caducó o debe reconectarse. Vuelve a ejecutar `/register` en Discord.",
+                "Seu vínculo com o AniList expirou ou precisa ser reconectado. Execute `/register` novamente no Discord.",
+            ],
+            MessageKey::ContextInvalid => [
+                "Invalid or expired OAuth context",
+                "OAuth コンテキストが無効か、有効期限が切れています",
+                "El contexto OAuth no es válido o ha caducado",
+                "O contexto OAuth é inválido ou expirou",
+            ],
+            MessageKey::StartFailed => [
+                "Failed to start the AniList login. Please try again.",
+                "AniList へのログインを開始できませんでした。もう一度お試しください。",
+                "No se pudo iniciar sesión en AniList. Inténtalo de nuevo.",
+                "Não foi possível iniciar o login no AniList. Tente novamente.",
+            ],
+            MessageKey::SessionCreateFailed => [
+                "Failed to create OAuth session. Please try again.",
+                "OAuth セッションを作成できませんでした。もう一度お試しください。",
+                "No se pudo crear la sesión OAuth. Inténtalo de nuevo.",
+                "Não foi possível criar a sessão OAuth. Tente novamente.",
+            ],
+            MessageKey::UpstreamDegraded => [
+                "AniList is having issues right now. Please try linking again in a few minutes.",
+                "AniList で障害が発生しています。数分後にもう一度連携をお試しください。",
+                "AniList tiene problemas en este momento. Intenta vincular de nuevo en unos minutos.",
+                "O AniList está com problemas no momento. Tente vincular novamente em alguns minutos.",
+            ],
+            MessageKey::ErrorBadRequest => [
+                "The request was malformed. Please restart the AniList login flow from Discord.",
+                "リクエストの形式が正しくありません。Discord から AniList へのログインをやり直してください。",
+                "La solicitud no es válida. Vuelve a iniciar sesión en AniList desde Discord.",
+                "A solicitação está malformada. Reinicie o login no AniList pelo Discord.",
+            ],
+            MessageKey::ErrorForbidden => [
+                "You don't have permission to view this page.",
+                "このページを表示する権限がありません。",
+                "No tienes permiso para ver esta página.",
+                "Você não tem permissão para ver esta página.",
+            ],
+            MessageKey::ErrorUnprocessable => [
+                "The request contained invalid data. Please restart the AniList login flow from Discord.",
+                "リクエストに無効なデータが含まれていました。Discord から AniList へのログインをやり直してください。",
+                "La solicitud contenía datos no válidos. Vuelve a iniciar sesión en AniList desde Discord.",
+                "A solicitação continha dados inválidos. Reinicie o login no AniList pelo Discord.",
+            ],
+            MessageKey::ErrorTooManyRequests => [
+                "Too many attempts. Please wait a minute before trying again.",
+                "試行回数が多すぎます。1 分ほど待ってからもう一度お試しください。",
+                "Demasiados intentos. Espera un minuto antes de volver a intentarlo.",
+                "Muitas tentativas. Aguarde um minuto antes de tentar novamente.",
+            ],
+            MessageKey::ErrorInternal => [
+                "Something went wrong on our side. Please try again in a moment.",
+                "サーバー側で問題が発生しました。しばらくしてからもう一度お試しください。",
+                "Algo salió mal de nuestro lado. Inténtalo de nuevo en un momento.",
+                "Algo deu errado do nosso lado. Tente novamente em instantes.",
+            ],
+            MessageKey::ErrorUnavailable => [
+                "Annie Mei is temporarily unavailable. Please try again shortly.",
+                "サービスは一時的に利用できません。しばらくしてからもう一度お試しください。",
+                "El servicio no está disponible temporalmente. Inténtalo de nuevo en breve.",
+                "O serviço está temporariamente indisponível. Tente novamente em breve.",
+            ],
+            MessageKey::ErrorGeneric => [
+                "Something went wrong. Please try again from Discord.",
+                "問題が発生しました。Discord からもう一度お試しください。",
+                "Algo salió mal. Inténtalo de nuevo desde Discord.",
+                "Algo deu errado. Tente novamente pelo Discord.",
+            ],
+            MessageKey::PageTitleConnected => ["Connected", "連携完了", "Conectado", "Conectado"],
+            MessageKey::PageTitleError => ["Error", "エラー", "Error", "Erro"],
+            MessageKey::PageHeadingConnected => [
+                "Account Connected",
+                "アカウントを連携しました",
+                "Cuenta conectada",
+                "Conta conectada",
+            ],
+            MessageKey::PageHeadingError => [
+                "Something Went Wrong",
+                "問題が発生しました",
+                "Algo salió mal",
+                "Algo deu errado",
+            ],
+            MessageKey::PageHintConnected => [
+                "You can close this tab now.",
+                "このタブは閉じて構いません。",
+                "Ya puedes cerrar esta pestaña.",
+                "Você já pode fechar esta aba.",
+            ],
+            MessageKey::PageHintError => [
+                "Please try again from Discord.",
+                "Discord からもう一度お試しください。",
+                "Inténtalo de nuevo desde Discord.",
+                "Tente novamente pelo Discord.",
+            ],
+            MessageKey::PageReferenceLabel => ["Reference", "参照 ID", "Referencia", "Referência"],
+            MessageKey::NotFoundHeading => [
+                "Page Not Found",
+                "ページが見つかりません",
+                "Página no encontrada",
+                "Página não encontrada",
+            ],
+            MessageKey::NotFoundMessage => [
+                "There's nothing here. If you're trying to link your AniList account, start from the bot command in Discord.",
+                "ここには何もありません。AniList アカウントを連携するには、Discord のボットコマンドから始めてください。",
+                "No hay nada aquí. Si quieres vincular tu cuenta de AniList, empieza con el comando del bot en Discord.",
+                "Não há nada aqui. Se você quer vincular sua conta do AniList, comece pelo comando do bot no Discord.",
+            ],
+            MessageKey::ConsentTitle => [
+                "Link AniList",
+                "AniList 連携",
+                "Vincular AniList",
+                "Vincular AniList",
+            ],
+            MessageKey::ConsentHeading => [
+                "Link your AniList account",
+                "AniList アカウントを連携",
+                "Vincula tu cuenta de AniList",
+                "Vincule sua conta do AniList",
+            ],
+            MessageKey::ConsentIntro => [
+                "You're about to link AniList to this Discord account:",
+                "次の Discord アカウントに AniList を連携しようとしています:",
+                "Estás a punto de vincular AniList a esta cuenta de Discord:",
+                "Você está prestes a vincular o AniList a esta conta do Discord:",
+            ],
+            MessageKey::ConsentPermissions => [
+                "The bot will be able to read your AniList profile and lists so it can show them in Discord.",
+                "ボットは AniList のプロフィールとリストを読み取り、Discord に表示できるようになります。",
+                "El bot podrá leer tu perfil y tus listas de AniList para mostrarlos en Discord.",
+                "O bot poderá ler seu perfil e suas listas do AniList para exibi-los no Discord.",
+            ],
+            MessageKey::ConsentPrivacy => [
+                "We store your AniList user ID and access token alongside your Discord user ID and use them only for bot commands. You can ask for your account to be unlinked at any time.",
+                "AniList のユーザー ID とアクセストークンを Discord のユーザー ID と紐付けて保存し、ボットのコマンドにのみ使用します。連携はいつでも解除を依頼できます。",
+                "Guardamos tu ID de usuario y token de acceso de AniList junto a tu ID de Discord y solo los usamos para los comandos del bot. Puedes pedir que se desvincule tu cuenta en cualquier momento.",
+                "Armazenamos seu ID de usuário e token de acesso do AniList junto ao seu ID do Discord e os usamos apenas para os comandos do bot. Você pode pedir a desvinculação da sua conta a qualquer momento.",
+            ],
+            MessageKey::ConsentContinue => [
+                "Continue to AniList",
+                "AniList に進む",
+                "Continuar a AniList",
+                "Continuar para o AniList",
+            ],
+            MessageKey::ConsentWrongAccount => [
+                "Not you? Close this page and run the command from your own Discord account.",
+                "別のアカウントですか？このページを閉じて、ご自身の Discord アカウントからコマンドを実行してください。",
+                "¿No eres tú? Cierra esta página y ejecuta el comando desde tu propia cuenta de Discord.",
+                "Não é você? Feche esta página e execute o comando a partir da sua própria conta do Discord.",
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Locale, MessageKey};
+
+    #[test]
+    fn from_tag_uses_primary_subtag() {
+        assert_eq!(Locale::from_tag("ja"), Some(Locale::Ja));
+        assert_eq!(Locale::from_tag("pt-BR"), Some(Locale::Pt));
+        assert_eq!(Locale::from_tag("es-419"), Some(Locale::Es));
+        assert_eq!(Locale::from_tag("EN_us"), Some(Locale::En));
+        assert_eq!(Locale::from_tag("fr"), None);
+    }
+
+    #[test]
+    fn negotiate_respects_quality_values() {
+        assert_eq!(
+            Locale::negotiate("fr-FR, es;q=0.4, ja;q=0.9"),
+            Some(Locale::Ja)
+        );
+        assert_eq!(
+            Locale::negotiate("pt-BR,pt;q=0.9,en;q=0.8"),
+            Some(Locale::Pt)
+        );
+        assert_eq!(Locale::negotiate("ja;q=0, es;q=0.1"), Some(Locale::Es));
+        assert_eq!(Locale::negotiate("de, fr"), None);
+        assert_eq!(Locale::negotiate(""), None);
+    }
+
+    #[test]
+    fn catalog_is_translated_for_every_locale() {
+        let english = MessageKey::RelinkRequired.text(Locale::En);
+
+        for locale in Locale::ALL {
+            let text = MessageKey::RelinkRequired.text(locale);
+            assert!(!text.is_empty());
+            if locale != Locale::En {
+                assert_ne!(text, english, "{locale:?} should not fall back to English");
+            }
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use super::{heartbeat::JobHeartbeats, structs::MyState};
+
+use rocket::{
+    fairing::AdHoc,
+    tokio::{self, sync::Notify, task::JoinHandle, time::timeout},
+};
+use std::{
+    future::Future,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+#[derive(Debug, Default)]
+struct Inner {
+    draining: AtomicBool,
+    drain_started: Notify,
+    in_flight: AtomicUsize,
+    idle: Notify,
+    tasks: Mutex<Vec<JoinHandle<()>>>,
+    heartbeats: JobHeartbeats,
+}
+
+/// Coordinates shutdown: new logins are refused once draining starts, in-flight
+/// callbacks and background jobs get to finish. Clones share state.
+#[derive(Debug, Clone, Default)]
+pub struct Lifecycle {
+    inner: Arc<Inner>,
+}
+
+/// Held while a callback runs; shutdown waits until every guard is dropped.
+#[must_use]
+pub struct InFlight(Lifecycle);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if self.0.inner.in_flight.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.0.inner.idle.notify_waiters();
+        }
+    }
+}
+
+impl Lifecycle {
+    pub fn is_draining(&self) -> bool {
+        self.inner.draining.load(Ordering::Acquire)
+    }
+
+    pub fn heartbeats(&self) -> &JobHeartbeats {
+        &self.inner.heartbeats
+    }
+
+    pub fn track(&self) -> InFlight {
+        self.inner.in_flight.fetch_add(1, Ordering::AcqRel);
+        InFlight(self.clone())
+    }
+
+    /// Spawns a background task that shutdown waits for.
+    pub fn spawn<F>(&self, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let handle = tokio::spawn(task);
+        self.inner
+            .tasks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(handle);
+    }
+
+    /// Resolves once draining has started. Background jobs select on this to stop early.
+    pub async fn draining(&self) {
+        loop {
+            let notified = self.inner.drain_started.notified();
+            if self.is_draining() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    /// Stops new work and waits up to `grace` for in-flight callbacks and background
+    /// tasks. Returns `false` if the grace period ran out first.
+    pub async fn drain(&self, grace: Duration) -> bool {
+        self.inner.draining.store(true, Ordering::Release);
+        self.inner.drain_started.notify_waiters();
+
+        let tasks = std::mem::take(
+            &mut *self
+                .inner
+                .tasks
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        );
+        let finished = async {
+            loop {
+                let notified = self.inner.idle.notified();
+                if self.inner.in_flight.load(Ordering::Acquire) == 0 {
+                    break;
+                }
+                notified.await;
+            }
+            for task in tasks {
+                let _ = task.await;
+            }
+        };
+
+        timeout(grace, finished).await.is_ok()
+    }
+}
+
+/// Drains `MyState`'s lifecycle when Rocket shuts down. Rocket keeps serving in-flight
+/// requests while shutdown fairings run, so `grace` should match `shutdown.grace`.
+pub fn drain_on_shutdown(grace: Duration) -> AdHoc {
+    AdHoc::on_shutdown("Drain in-flight work", move |rocket| {
+        Box::pin(async move {
+            let Some(state) = rocket.state::<MyState>() else {
+                return;
+            };
+
+            info!("Draining in-flight callbacks and background jobs");
+            if !state.lifecycle.drain(grace).await {
+                warn!("Shutdown grace period ended with work still in flight");
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Lifecycle;
+    use rocket::tokio::{self, time::sleep};
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicBool, Ordering},
+        },
+        time::Duration,
+    };
+
+    #[rocket::async_test]
+    async fn drain_waits_for_in_flight_work_and_tasks() {
+        let lifecycle = Lifecycle::default();
+        let guard = lifecycle.track();
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let task_lifecycle = lifecycle.clone();
+        let task_stopped = stopped.clone();
+        lifecycle.spawn(async move {
+            task_lifecycle.draining().await;
+            task_stopped.store(true, Ordering::Release);
+        });
+
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(50)).await;
+            drop(guard);
+        });
+
+        assert!(lifecycle.drain(Duration::from_secs(5)).await);
+        assert!(lifecycle.is_draining());
+        assert!(stopped.load(Ordering::Acquire));
+    }
+
+    #[rocket::async_test]
+    async fn drain_survives_a_poisoned_task_lock() {
+        let lifecycle = Lifecycle::default();
+        let poisoner = lifecycle.clone();
+        std::thread::spawn(move || {
+            let _tasks = poisoner.inner.tasks.lock();
+            panic!("poison the task lock");
+        })
+        .join()
+        .expect_err("the thread should panic");
+        assert!(lifecycle.inner.tasks.is_poisoned());
+
+        let task_lifecycle = lifecycle.clone();
+        lifecycle.spawn(async move { task_lifecycle.draining().await });
+        lifecycle
+            .heartbeats()
+            .register("job", Duration::from_secs(60));
+
+        assert!(lifecycle.drain(Duration::from_secs(5)).await);
+    }
+
+    #[rocket::async_test]
+    async fn drain_gives_up_after_grace() {
+        let lifecycle = Lifecycle::default();
+        let _guard = lifecycle.track();
+
+        assert!(!lifecycle.drain(Duration::from_millis(20)).await);
+    }
+}
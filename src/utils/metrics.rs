@@ -0,0 +1,23 @@
+use anyhow::{Context, Result};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+
+pub const RETENTION_RUNS_TOTAL: &str = "annie_mei_retention_runs_total";
+pub const RETENTION_ROWS_TOTAL: &str = "annie_mei_retention_rows_total";
+pub const RETENTION_LAST_SUCCESS_SECONDS: &str =
+    "annie_mei_retention_last_success_timestamp_seconds";
+pub const CREDENTIAL_PROBE_RUNS_TOTAL: &str = "annie_mei_credential_probe_runs_total";
+pub const CREDENTIAL_PROBES_TOTAL: &str = "annie_mei_credential_probes_total";
+pub const CREDENTIAL_PROBE_LAST_SUCCESS_SECONDS: &str =
+    "annie_mei_credential_probe_last_success_timestamp_seconds";
+pub const LINKED_CREDENTIALS: &str = "annie_mei_linked_credentials";
+/// `0` closed, `1` half-open, `2` open.
+pub const ANILIST_CIRCUIT_STATE: &str = "annie_mei_anilist_circuit_state";
+pub const ANILIST_CIRCUIT_TRIPS_TOTAL: &str = "annie_mei_anilist_circuit_trips_total";
+
+/// Installs the global Prometheus recorder. The returned handle renders the scrape
+/// body for `/admin/metrics`.
+pub fn install_prometheus_recorder() -> Result<PrometheusHandle> {
+    PrometheusBuilder::new()
+        .install_recorder()
+        .context("Failed to install Prometheus metrics recorder")
+}
@@ -1,5 +1,22 @@
+pub mod anilist_client;
+pub mod circuit_breaker;
+pub mod config;
 pub mod consts;
+pub mod database;
+pub mod fairings;
 pub mod functions;
 pub mod guards;
+pub mod heartbeat;
+pub mod i18n;
+pub mod lifecycle;
+pub mod metrics;
 pub mod observability;
+pub mod pages;
+pub mod probe;
+pub mod rate_limit;
+pub mod redis_sessions;
+pub mod reload;
+pub mod retention;
+pub mod sqlite;
+pub mod store;
 pub mod structs;
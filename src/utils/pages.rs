@@ -0,0 +1,353 @@
+use super::i18n::{Locale, MessageKey};
+
+use anyhow::{Context, Result};
+use minijinja::{Environment, context};
+use rocket::figment::{
+    Figment,
+    providers::{Format, Serialized, Toml},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::Path,
+    sync::{Arc, LazyLock},
+};
+
+const LAYOUT_TEMPLATE: &str = "layout.html";
+const RESULT_TEMPLATE: &str = "result.html";
+const NOT_FOUND_TEMPLATE: &str = "not_found.html";
+const CONSENT_TEMPLATE: &str = "consent.html";
+const THEME_FILE: &str = "theme.toml";
+
+const EMBEDDED_TEMPLATES: [(&str, &str); 4] = [
+    (LAYOUT_TEMPLATE, include_str!("../../templates/layout.html")),
+    (RESULT_TEMPLATE, include_str!("../../templates/result.html")),
+    (
+        NOT_FOUND_TEMPLATE,
+        include_str!("../../templates/not_found.html"),
+    ),
+    (
+        CONSENT_TEMPLATE,
+        include_str!("../../templates/consent.html"),
+    ),
+];
+
+static EMBEDDED_PAGES: LazyLock<Pages> =
+    LazyLock::new(|| Pages::load(None).expect("embedded templates should always compile"));
+
+/// Branding values available to every template as `theme`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Theme {
+    pub brand_name: String,
+    pub favicon_url: String,
+    pub background_start: String,
+    pub background_end: String,
+    pub text_color: String,
+    pub muted_color: String,
+    pub success_color: String,
+    pub error_color: String,
+    pub highlight_color: String,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            brand_name: "Annie Mei".to_string(),
+            favicon_url: "/static/favicon.png".to_string(),
+            background_start: "#0f0f13".to_string(),
+            background_end: "#1a1a2e".to_string(),
+            text_color: "#e4e4e7".to_string(),
+            muted_color: "#a1a1aa".to_string(),
+            success_color: "#22c55e".to_string(),
+            error_color: "#ef4444".to_string(),
+            highlight_color: "#a78bfa".to_string(),
+        }
+    }
+}
+
+/// The account a consent page is about to link, plus the signed context to post back.
+#[derive(Debug)]
+pub struct ConsentDetails<'a> {
+    pub ctx: &'a str,
+    pub discord_user_id: &'a str,
+    pub discord_username: Option<&'a str>,
+    pub avatar_url: Option<&'a str>,
+}
+
+impl ConsentDetails<'_> {
+    /// Placeholder values used to validate the consent template at load time.
+    fn preview() -> Self {
+        Self {
+            ctx: "",
+            discord_user_id: "",
+            discord_username: Some(""),
+            avatar_url: Some(""),
+        }
+    }
+}
+
+/// Compiled HTML templates plus the active theme. Cheap to clone.
+#[derive(Clone)]
+pub struct Pages {
+    env: Arc<Environment<'static>>,
+    theme: Arc<Theme>,
+}
+
+impl Pages {
+    /// Loads the embedded templates, replacing any that exist in `dir` and
+    /// merging `dir/theme.toml` over the default theme.
+    pub fn load(dir: Option<&Path>) -> Result<Self> {
+        let mut env = Environment::new();
+
+        for (name, embedded) in EMBEDDED_TEMPLATES {
+            let override_path = dir.map(|dir| dir.join(name)).filter(|path| path.is_file());
+            let source = match override_path {
+                Some(path) => fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read template {}", path.display()))?,
+                None => embedded.to_string(),
+            };
+
+            env.add_template_owned(name, source)
+                .with_context(|| format!("Failed to compile template {name}"))?;
+        }
+
+        let mut figment = Figment::from(Serialized::defaults(Theme::default()));
+        if let Some(dir) = dir {
+            figment = figment.merge(Toml::file(dir.join(THEME_FILE)));
+        }
+        let theme = figment.extract().context("Failed to load theme.toml")?;
+
+        let pages = Self {
+            env: Arc::new(env),
+            theme: Arc::new(theme),
+        };
+
+        // Surface broken overrides at startup instead of on the first error page.
+        for locale in Locale::ALL {
+            pages.try_render_result(true, "", "", None, locale)?;
+            pages.try_render_result(false, "", "", Some(""), locale)?;
+            pages.try_render_not_found("", None, locale)?;
+            pages.try_render_consent(&ConsentDetails::preview(), "", Some(""), locale)?;
+        }
+
+        Ok(pages)
+    }
+
+    /// The built-in templates and theme, for callers without managed state.
+    pub fn embedded() -> &'static Self {
+        &EMBEDDED_PAGES
+    }
+
+    /// The success or error card shown after the OAuth flow. `message` is
+    /// expected to already be localized.
+    pub fn render_result(
+        &self,
+        success: bool,
+        message: &str,
+        nonce: &str,
+        request_id: Option<&str>,
+        locale: Locale,
+    ) -> String {
+        self.try_render_result(success, message, nonce, request_id, locale)
+            .unwrap_or_else(|error| render_failure(&error, locale))
+    }
+
+    pub fn render_not_found(
+        &self,
+        nonce: &str,
+        request_id: Option<&str>,
+        locale: Locale,
+    ) -> String {
+        self.try_render_not_found(nonce, request_id, locale)
+            .unwrap_or_else(|error| render_failure(&error, locale))
+    }
+
+    pub fn render_consent(
+        &self,
+        consent: &ConsentDetails<'_>,
+        nonce: &str,
+        request_id: Option<&str>,
+        locale: Locale,
+    ) -> String {
+        self.try_render_consent(consent, nonce, request_id, locale)
+            .unwrap_or_else(|error| render_failure(&error, locale))
+    }
+
+    fn try_render_result(
+        &self,
+        success: bool,
+        message: &str,
+        nonce: &str,
+        request_id: Option<&str>,
+        locale: Locale,
+    ) -> Result<String> {
+        let (accent, title, heading, hint) = if success {
+            (
+                &self.theme.success_color,
+                MessageKey::PageTitleConnected,
+                MessageKey::PageHeadingConnected,
+                MessageKey::PageHintConnected,
+            )
+        } else {
+            (
+                &self.theme.error_color,
+                MessageKey::PageTitleError,
+                MessageKey::PageHeadingError,
+                MessageKey::PageHintError,
+            )
+        };
+
+        self.env
+            .get_template(RESULT_TEMPLATE)?
+            .render(context! {
+                theme => &*self.theme,
+                lang => locale.as_str(),
+                accent,
+                success,
+                title => title.text(locale),
+                heading => heading.text(locale),
+                hint => hint.text(locale),
+                reference_label => MessageKey::PageReferenceLabel.text(locale),
+                message,
+                nonce,
+                request_id,
+            })
+            .context("Failed to render result page")
+    }
+
+    fn try_render_not_found(
+        &self,
+        nonce: &str,
+        request_id: Option<&str>,
+        locale: Locale,
+    ) -> Result<String> {
+        self.env
+            .get_template(NOT_FOUND_TEMPLATE)?
+            .render(context! {
+                theme => &*self.theme,
+                lang => locale.as_str(),
+                accent => &self.theme.highlight_color,
+                heading => MessageKey::NotFoundHeading.text(locale),
+                message => MessageKey::NotFoundMessage.text(locale),
+                reference_label => MessageKey::PageReferenceLabel.text(locale),
+                nonce,
+                request_id,
+            })
+            .context("Failed to render not found page")
+    }
+
+    fn try_render_consent(
+        &self,
+        consent: &ConsentDetails<'_>,
+        nonce: &str,
+        request_id: Option<&str>,
+        locale: Locale,
+    ) -> Result<String> {
+        self.env
+            .get_template(CONSENT_TEMPLATE)?
+            .render(context! {
+                theme => &*self.theme,
+                lang => locale.as_str(),
+                accent => &self.theme.highlight_color,
+                title => MessageKey::ConsentTitle.text(locale),
+                heading => MessageKey::ConsentHeading.text(locale),
+                intro => MessageKey::ConsentIntro.text(locale),
+                permissions => MessageKey::ConsentPermissions.text(locale),
+                privacy => MessageKey::ConsentPrivacy.text(locale),
+                continue_label => MessageKey::ConsentContinue.text(locale),
+                wrong_account => MessageKey::ConsentWrongAccount.text(locale),
+                reference_label => MessageKey::PageReferenceLabel.text(locale),
+                ctx => consent.ctx,
+                discord_user_id => consent.discord_user_id,
+                discord_username => consent.discord_username,
+                avatar_url => consent.avatar_url,
+                nonce,
+                request_id,
+            })
+            .context("Failed to render consent page")
+    }
+}
+
+fn render_failure(error: &anyhow::Error, locale: Locale) -> String {
+    error!("Failed to render HTML page: {error:#}");
+    MessageKey::ErrorGeneric.text(locale).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Pages;
+    use crate::utils::i18n::Locale;
+    use std::fs;
+
+    #[test]
+    fn embedded_result_page_escapes_message() {
+        let html = Pages::embedded().render_result(
+            false,
+            "<script>alert(\"x\")</script>",
+            "nonce-123",
+            Some("req-1"),
+            Locale::En,
+        );
+
+        assert!(html.contains("Something Went Wrong"));
+        assert!(html.contains("&lt;script&gt;"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains(r#"<style nonce="nonce-123">"#));
+        assert!(html.contains("Reference: <code>req-1</code>"));
+        assert!(html.contains("#ef4444"));
+    }
+
+    #[test]
+    fn success_page_omits_reference_without_request_id() {
+        let html = Pages::embedded().render_result(true, "Linked", "nonce", None, Locale::En);
+
+        assert!(html.contains("Account Connected"));
+        assert!(!html.contains("Reference:"));
+    }
+
+    #[test]
+    fn pages_render_in_requested_locale() {
+        let html = Pages::embedded().render_result(false, "x", "nonce", Some("req-1"), Locale::Ja);
+
+        assert!(html.contains(r#"<html lang="ja">"#));
+        assert!(html.contains("問題が発生しました"));
+        assert!(html.contains("参照 ID: <code>req-1</code>"));
+
+        let not_found = Pages::embedded().render_not_found("nonce", None, Locale::Es);
+        assert!(not_found.contains("Página no encontrada"));
+    }
+
+    #[test]
+    fn template_dir_overrides_theme_and_templates() {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        fs::write(
+            dir.path().join("theme.toml"),
+            "brand_name = \"Sister Bot\"\nerror_color = \"#123456\"\n",
+        )
+        .expect("theme should be written");
+        fs::write(
+            dir.path().join("not_found.html"),
+            "{% extends \"layout.html\" %}{% block title %}Lost{% endblock %}\
+             {% block content %}<h1>Nothing to see</h1>{% endblock %}",
+        )
+        .expect("template should be written");
+
+        let pages = Pages::load(Some(dir.path())).expect("overrides should load");
+
+        let not_found = pages.render_not_found("nonce", None, Locale::En);
+        assert!(not_found.contains("Nothing to see"));
+        assert!(not_found.contains("<title>Lost - Sister Bot</title>"));
+
+        let error = pages.render_result(false, "Nope", "nonce", None, Locale::En);
+        assert!(error.contains("#123456"));
+        assert!(error.contains("Sister Bot"));
+    }
+
+    #[test]
+    fn broken_override_fails_at_load() {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        fs::write(dir.path().join("result.html"), "{% if %}").expect("template should be written");
+
+        assert!(Pages::load(Some(dir.path())).is_err());
+    }
+}
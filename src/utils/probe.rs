@@ -0,0 +1,450 @@
+use super::{
+    anilist_client::AniListClient,
+    circuit_breaker::CircuitState,
+    functions::{
+        AUDIT_ACTOR_SYSTEM, CredentialVerificationError, count_oauth_credentials_by_state,
+        sample_oauth_credentials_for_probe, verify_oauth_credential,
+    },
+    lifecycle::Lifecycle,
+    metrics::{
+        CREDENTIAL_PROBE_LAST_SUCCESS_SECONDS, CREDENTIAL_PROBE_RUNS_TOTAL,
+        CREDENTIAL_PROBES_TOTAL, LINKED_CREDENTIALS,
+    },
+    observability::configure_oauth_scope,
+    structs::CredentialHealth,
+};
+
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::{Pool, Postgres};
+use std::time::Duration;
+
+pub const CREDENTIAL_PROBE_JOB: &str = "credential_probe";
+
+/// How many credentials each run checks and how fast it talks to AniList.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProbeSettings {
+    pub batch_size: i64,
+    /// Upper bound on `Viewer` requests per minute, kept well below AniList's own limit
+    /// so the bot keeps its share.
+    pub requests_per_minute: i64,
+}
+
+impl Default for ProbeSettings {
+    fn default() -> Self {
+        Self {
+            batch_size: 50,
+            requests_per_minute: 20,
+        }
+    }
+}
+
+impl ProbeSettings {
+    fn pace(&self) -> Duration {
+        let requests_per_minute =
+            u32::try_from(self.requests_per_minute.max(1)).unwrap_or(u32::MAX);
+        Duration::from_secs(60) / requests_per_minute
+    }
+}
+
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub struct ProbeReport {
+    pub valid: u64,
+    pub token_revoked: u64,
+    pub anilist_id_mismatch: u64,
+    pub relink_required: u64,
+    pub failed: u64,
+}
+
+impl ProbeReport {
+    fn record(&mut self, outcome: &'static str) {
+        metrics::counter!(CREDENTIAL_PROBES_TOTAL, "outcome" => outcome).increment(1);
+        match outcome {
+            "valid" => self.valid += 1,
+            "token_revoked" => self.token_revoked += 1,
+            "anilist_id_mismatch" => self.anilist_id_mismatch += 1,
+            "relink_required" => self.relink_required += 1,
+            _ => self.failed += 1,
+        }
+    }
+}
+
+const fn outcome_label(health: CredentialHealth) -> &'static str {
+    match health {
+        CredentialHealth::Valid => "valid",
+        CredentialHealth::TokenRevoked => "token_revoked",
+        CredentialHealth::AnilistIdMismatch => "anilist_id_mismatch",
+        CredentialHealth::RelinkRequired => "relink_required",
+    }
+}
+
+/// Verifies the least recently probed credentials against AniList, waiting `pace`
+/// between requests. AniList failures are counted and skipped; database errors end
+/// the run. Stops early once shutdown starts draining, since a full batch takes
+/// far longer than the shutdown grace period.
+pub async fn probe_credentials(
+    client: &AniListClient,
+    user_endpoint: &str,
+    settings: &ProbeSettings,
+    user_id_hash_salt: &str,
+    db: &Pool<Postgres>,
+    lifecycle: &Lifecycle,
+) -> Result<ProbeReport, sqlx::Error> {
+    let mut report = ProbeReport::default();
+    if client.breaker().state() == CircuitState::Open {
+        info!("Skipping credential probe: AniList circuit breaker is open");
+        return Ok(report);
+    }
+
+    let sample = sample_oauth_credentials_for_probe(settings.batch_size, db).await?;
+
+    for (index, discord_user_id) in sample.iter().enumerate() {
+        if index > 0 {
+            rocket::tokio::select! {
+                biased;
+                _ = lifecycle.draining() => {
+                    info!("Stopping credential probe: shutting down");
+                    break;
+                }
+                _ = rocket::tokio::time::sleep(settings.pace()) => {}
+            }
+        }
+        if !client.breaker().allows_requests() {
+            info!("Stopping credential probe: AniList circuit breaker opened");
+            break;
+        }
+
+        let verification = rocket::tokio::select! {
+            biased;
+            _ = lifecycle.draining() => {
+                info!("Stopping credential probe: shutting down");
+                break;
+            }
+            verification = verify_oauth_credential(
+                client,
+                user_endpoint,
+                discord_user_id,
+                AUDIT_ACTOR_SYSTEM,
+                user_id_hash_salt,
+                db,
+            ) => verification,
+        };
+        match verification {
+            Ok(health) => report.record(outcome_label(health)),
+            // Unlinked since the sample was taken.
+            Err(CredentialVerificationError::Missing) => {}
+            Err(CredentialVerificationError::Upstream(_)) => report.record("failed"),
+            Err(CredentialVerificationError::Db(e)) => return Err(e),
+        }
+    }
+
+    let (usable, relink_required) = count_oauth_credentials_by_state(db).await?;
+    metrics::gauge!(LINKED_CREDENTIALS, "state" => "usable").set(usable as f64);
+    metrics::gauge!(LINKED_CREDENTIALS, "state" => "relink_required").set(relink_required as f64);
+
+    Ok(report)
+}
+
+/// One scheduled run: probes a batch and reports the outcome through tracing, metrics
+/// and (on failure) Sentry.
+#[tracing::instrument(name = "credential_probe.run", skip_all)]
+pub async fn run_credential_probe(
+    client: &AniListClient,
+    user_endpoint: &str,
+    settings: &ProbeSettings,
+    user_id_hash_salt: &str,
+    db: &Pool<Postgres>,
+    lifecycle: &Lifecycle,
+) {
+    match probe_credentials(
+        client,
+        user_endpoint,
+        settings,
+        user_id_hash_salt,
+        db,
+        lifecycle,
+    )
+    .await
+    {
+        Ok(report) => {
+            metrics::counter!(CREDENTIAL_PROBE_RUNS_TOTAL, "outcome" => "success").increment(1);
+            metrics::gauge!(CREDENTIAL_PROBE_LAST_SUCCESS_SECONDS)
+                .set(Utc::now().timestamp() as f64);
+
+            info!(
+                "Credential probe finished: {} valid, {} revoked, {} AniList ID mismatches, \
+                 {} already relink-required, {} failed",
+                report.valid,
+                report.token_revoked,
+                report.anilist_id_mismatch,
+                report.relink_required,
+                report.failed
+            );
+        }
+        Err(e) => {
+            metrics::counter!(CREDENTIAL_PROBE_RUNS_TOTAL, "outcome" => "failure").increment(1);
+            sentry::with_scope(
+                |scope| configure_oauth_scope(scope, "credential_probe.run", None),
+                || sentry::capture_error(&e),
+            );
+            error!("Credential probe run failed: {e}");
+        }
+    }
+}
+
+/// Probes a batch every `interval`, starting immediately, until shutdown begins draining.
+pub fn spawn_credential_probe_job(
+    settings: ProbeSettings,
+    interval: Duration,
+    client: AniListClient,
+    user_endpoint: String,
+    user_id_hash_salt: String,
+    db: Pool<Postgres>,
+    lifecycle: Lifecycle,
+) {
+    lifecycle
+        .heartbeats()
+        .register(CREDENTIAL_PROBE_JOB, interval);
+    let task_lifecycle = lifecycle.clone();
+    lifecycle.spawn(async move {
+        let mut ticker = rocket::tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(rocket::tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            rocket::tokio::select! {
+                biased;
+                _ = task_lifecycle.draining() => break,
+                _ = ticker.tick() => {}
+            }
+            run_credential_probe(
+                &client,
+                &user_endpoint,
+                &settings,
+                &user_id_hash_salt,
+                &db,
+                &task_lifecycle,
+            )
+            .await;
+            task_lifecycle.heartbeats().beat(CREDENTIAL_PROBE_JOB);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProbeReport, ProbeSettings, probe_credentials, run_credential_probe};
+    use crate::utils::anilist_client::AniListClient;
+    use crate::utils::functions::{fetch_audit_events, fetch_credential_by_discord_user};
+    use crate::utils::lifecycle::Lifecycle;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use rocket::tokio::time::{sleep, timeout};
+    use serde_json::json;
+    use sqlx::{Pool, Postgres};
+    use std::time::Duration;
+    use wiremock::{
+        Mock, MockServer, ResponseTemplate,
+        matchers::{header, method},
+    };
+
+    const TEST_USERID_HASH_SALT: &str = "test-userid-hash-salt";
+
+    const FAST: ProbeSettings = ProbeSettings {
+        batch_size: 10,
+        requests_per_minute: 60_000,
+    };
+
+    async fn seed(pool: &Pool<Postgres>) {
+        sqlx::query(
+            "INSERT INTO oauth_credentials \
+             (discord_user_id, anilist_id, access_token, relink_required_at, relink_reason) VALUES \
+             ('healthy', 1, 'healthy-token', NULL, NULL), \
+             ('revoked', 2, 'revoked-token', NULL, NULL), \
+             ('moved', 3, 'moved-token', NULL, NULL), \
+             ('flagged', 4, 'flagged-token', NOW(), 'token_expired')",
+        )
+        .execute(pool)
+        .await
+        .expect("credentials should insert");
+    }
+
+    async fn mock_anilist() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("authorization", "Bearer healthy-token"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "data": { "Viewer": { "id": 1 } } })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(header("authorization", "Bearer revoked-token"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(header("authorization", "Bearer moved-token"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "data": { "Viewer": { "id": 99 } } })),
+            )
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn probe_marks_revoked_and_mismatched_credentials(pool: Pool<Postgres>) {
+        seed(&pool).await;
+        let server = mock_anilist().await;
+        let client = AniListClient::default();
+
+        let report = probe_credentials(
+            &client,
+            &server.uri(),
+            &FAST,
+            TEST_USERID_HASH_SALT,
+            &pool,
+            &Lifecycle::default(),
+        )
+        .await
+        .expect("probe should run");
+        assert_eq!(
+            report,
+            ProbeReport {
+                valid: 1,
+                token_revoked: 1,
+                anilist_id_mismatch: 1,
+                relink_required: 0,
+                failed: 0,
+            }
+        );
+
+        for (discord_user_id, reason) in [
+            ("healthy", None),
+            ("revoked", Some("token_revoked")),
+            ("moved", Some("anilist_id_mismatch")),
+            ("flagged", Some("token_expired")),
+        ] {
+            let credential =
+                fetch_credential_by_discord_user(discord_user_id, TEST_USERID_HASH_SALT, &pool)
+                    .await
+                    .expect("fetch should not error")
+                    .expect("credential should exist");
+            assert_eq!(
+                credential.relink_reason.as_deref(),
+                reason,
+                "{discord_user_id}"
+            );
+        }
+
+        let audit = fetch_audit_events("moved", 10, TEST_USERID_HASH_SALT, &pool)
+            .await
+            .expect("audit events should load");
+        assert_eq!(audit[0].actor, "system");
+        assert_eq!(audit[0].reason.as_deref(), Some("anilist_id_mismatch"));
+
+        // Only the healthy credential is still sampled, and it was just probed.
+        let second = probe_credentials(
+            &client,
+            &server.uri(),
+            &FAST,
+            TEST_USERID_HASH_SALT,
+            &pool,
+            &Lifecycle::default(),
+        )
+        .await
+        .expect("probe should run again");
+        assert_eq!(second.valid, 1);
+        assert_eq!(second.token_revoked + second.anilist_id_mismatch, 0);
+
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn run_credential_probe_reports_metrics(pool: Pool<Postgres>) {
+        seed(&pool).await;
+        let server = mock_anilist().await;
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+
+        {
+            let _guard = metrics::set_default_local_recorder(&recorder);
+            run_credential_probe(
+                &AniListClient::default(),
+                &server.uri(),
+                &FAST,
+                TEST_USERID_HASH_SALT,
+                &pool,
+                &Lifecycle::default(),
+            )
+            .await;
+        }
+
+        let rendered = handle.render();
+        assert!(
+            rendered.contains("annie_mei_credential_probes_total{outcome=\"token_revoked\"} 1")
+        );
+        assert!(rendered.contains("annie_mei_credential_probe_runs_total{outcome=\"success\"} 1"));
+        assert!(rendered.contains("annie_mei_linked_credentials{state=\"relink_required\"} 3"));
+
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn probe_stops_mid_batch_when_draining(pool: Pool<Postgres>) {
+        seed(&pool).await;
+        let server = mock_anilist().await;
+        let client = AniListClient::default();
+        let uri = server.uri();
+        let lifecycle = Lifecycle::default();
+        // A minute between requests, so only the first credential is checked before
+        // the drain.
+        let slow = ProbeSettings {
+            batch_size: 10,
+            requests_per_minute: 1,
+        };
+
+        let probe = probe_credentials(
+            &client,
+            &uri,
+            &slow,
+            TEST_USERID_HASH_SALT,
+            &pool,
+            &lifecycle,
+        );
+        let drain = async {
+            sleep(Duration::from_millis(200)).await;
+            lifecycle.drain(Duration::from_secs(1)).await
+        };
+        let (report, drained) = timeout(Duration::from_secs(5), async {
+            rocket::tokio::join!(probe, drain)
+        })
+        .await
+        .expect("probe should stop once draining starts");
+
+        assert!(drained);
+        let report = report.expect("probe should run");
+        assert_eq!(
+            report.valid + report.token_revoked + report.anilist_id_mismatch,
+            1
+        );
+
+        pool.close().await;
+    }
+
+    #[test]
+    fn pace_spreads_requests_over_a_minute() {
+        let settings = ProbeSettings {
+            batch_size: 10,
+            requests_per_minute: 30,
+        };
+        assert_eq!(settings.pace(), std::time::Duration::from_secs(2));
+
+        let oversized = ProbeSettings {
+            batch_size: 10,
+            requests_per_minute: 4_294_967_296,
+        };
+        assert!(oversized.pace() > std::time::Duration::ZERO);
+    }
+}
@@ -0,0 +1,151 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Buckets beyond this count trigger a sweep of fully refilled (idle) entries. If
+/// none are idle, the least recently used bucket is evicted, so memory stays bounded.
+const MAX_TRACKED_KEYS: usize = 10_000;
+
+/// In-memory token buckets keyed by an arbitrary string. Each key may burst up to
+/// the per-minute limit and then refills continuously over the minute.
+pub struct RateLimiter {
+    capacity: f64,
+    refill_per_second: f64,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl RateLimiter {
+    pub fn per_minute(limit: i64) -> Self {
+        let capacity = limit.max(1) as f64;
+
+        Self {
+            capacity,
+            refill_per_second: capacity / 60.0,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes one token for `key`, returning `false` when the bucket is empty.
+    pub fn check(&self, key: &str) -> bool {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: &str, now: Instant) -> bool {
+        let mut buckets = self
+            .buckets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if buckets.len() >= MAX_TRACKED_KEYS && !buckets.contains_key(key) {
+            let full_after = Duration::from_secs_f64(60.0);
+            buckets.retain(|_, bucket| now.duration_since(bucket.updated_at) < full_after);
+
+            if buckets.len() >= MAX_TRACKED_KEYS
+                && let Some(oldest) = buckets
+                    .iter()
+                    .min_by_key(|(_, bucket)| bucket.updated_at)
+                    .map(|(key, _)| key.clone())
+            {
+                buckets.remove(&oldest);
+            }
+        }
+
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: self.capacity,
+            updated_at: now,
+        });
+
+        let elapsed = now.duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_second).min(self.capacity);
+        bucket.updated_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Limits applied to the OAuth entry points.
+pub struct RateLimits {
+    pub per_ip: RateLimiter,
+    pub per_discord_user: RateLimiter,
+}
+
+impl RateLimits {
+    pub fn per_minute(per_ip: i64, per_discord_user: i64) -> Self {
+        Self {
+            per_ip: RateLimiter::per_minute(per_ip),
+            per_discord_user: RateLimiter::per_minute(per_discord_user),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MAX_TRACKED_KEYS, RateLimiter};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn rate_limiter_allows_burst_then_rejects() {
+        let limiter = RateLimiter::per_minute(3);
+        let now = Instant::now();
+
+        assert!(limiter.check_at("ip", now));
+        assert!(limiter.check_at("ip", now));
+        assert!(limiter.check_at("ip", now));
+        assert!(!limiter.check_at("ip", now));
+    }
+
+    #[test]
+    fn rate_limiter_refills_over_time() {
+        let limiter = RateLimiter::per_minute(2);
+        let now = Instant::now();
+
+        assert!(limiter.check_at("ip", now));
+        assert!(limiter.check_at("ip", now));
+        assert!(!limiter.check_at("ip", now));
+
+        // Two per minute refills one token every 30 seconds.
+        assert!(!limiter.check_at("ip", now + Duration::from_secs(20)));
+        assert!(limiter.check_at("ip", now + Duration::from_secs(31)));
+    }
+
+    #[test]
+    fn rate_limiter_tracks_keys_independently() {
+        let limiter = RateLimiter::per_minute(1);
+        let now = Instant::now();
+
+        assert!(limiter.check_at("first", now));
+        assert!(!limiter.check_at("first", now));
+        assert!(limiter.check_at("second", now));
+    }
+
+    #[test]
+    fn rate_limiter_stays_bounded_under_a_flood_of_keys() {
+        let limiter = RateLimiter::per_minute(1);
+        let now = Instant::now();
+
+        assert!(limiter.check_at("first", now));
+        for index in 0..MAX_TRACKED_KEYS + 100 {
+            let at = now + Duration::from_millis(1 + index as u64);
+            assert!(limiter.check_at(&format!("flood-{index}"), at));
+        }
+
+        let buckets = limiter.buckets.lock().expect("rate limiter lock poisoned");
+        assert_eq!(buckets.len(), MAX_TRACKED_KEYS);
+        assert!(
+            !buckets.contains_key("first"),
+            "the oldest bucket should be evicted"
+        );
+    }
+}
@@ -0,0 +1,288 @@
+use crate::utils::{
+    functions::SessionConsumeError, i18n::Locale, observability::record_identifier_fingerprint,
+    store::SessionStore, structs::OAuthSession,
+};
+
+use chrono::{DateTime, Utc};
+use redis::{Client, RedisError, Script, aio::ConnectionManager};
+use serde::{Deserialize, Serialize};
+
+const KEY_PREFIX: &str = "oauth_session:";
+/// How long a session key outlives its expiry, so replays and late callbacks get
+/// `AlreadyUsed` or `Expired` rather than `NotFound`.
+const TOMBSTONE_SECONDS: i64 = 3600;
+
+/// Checks the session and marks it used in one step. Returns `{status}` on
+/// failure and `{"consumed", <session before marking>}` on success.
+const CONSUME_SCRIPT: &str = r"
+local raw = redis.call('GET', KEYS[1])
+if not raw then
+    return {'not_found'}
+end
+local session = cjson.decode(raw)
+if session.used_at then
+    return {'already_used'}
+end
+local now = tonumber(ARGV[1])
+if session.expires_at <= now then
+    return {'expired'}
+end
+if session.browser_binding_hash and session.browser_binding_hash ~= ARGV[2] then
+    return {'browser_mismatch'}
+end
+session.used_at = now
+redis.call('SET', KEYS[1], cjson.encode(session), 'KEEPTTL')
+return {'consumed', raw}
+";
+
+/// The JSON stored under each session key. Timestamps are Unix seconds and unset
+/// fields are omitted, since the consume script treats any present field as set.
+#[derive(Serialize, Deserialize)]
+struct StoredSession {
+    discord_user_id: String,
+    created_at: i64,
+    expires_at: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    used_at: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    locale: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    browser_binding_hash: Option<String>,
+}
+
+/// OAuth sessions in Redis, so several instances can share `start` and the
+/// callback. Keys expire on their own; nothing needs cleaning up.
+pub struct RedisSessionStore {
+    connection: ConnectionManager,
+    consume_script: Script,
+}
+
+impl RedisSessionStore {
+    pub async fn connect(url: &str) -> Result<Self, RedisError> {
+        let connection = ConnectionManager::new(Client::open(url)?).await?;
+        Ok(Self {
+            connection,
+            consume_script: Script::new(CONSUME_SCRIPT),
+        })
+    }
+}
+
+fn session_key(state: &str) -> String {
+    format!("{KEY_PREFIX}{state}")
+}
+
+fn redis_error(error: RedisError) -> sqlx::Error {
+    sqlx::Error::Io(std::io::Error::other(error))
+}
+
+fn timestamp(seconds: i64) -> Result<DateTime<Utc>, sqlx::Error> {
+    DateTime::from_timestamp(seconds, 0).ok_or_else(|| {
+        sqlx::Error::Decode(format!("session timestamp {seconds} is out of range").into())
+    })
+}
+
+#[rocket::async_trait]
+impl SessionStore for RedisSessionStore {
+    #[tracing::instrument(
+        name = "redis.insert_oauth_session",
+        skip_all,
+        fields(discord_user_fingerprint = tracing::field::Empty)
+    )]
+    async fn insert_oauth_session(
+        &self,
+        state: &str,
+        discord_user_id: &str,
+        ttl_seconds: i64,
+        locale: Option<Locale>,
+        browser_binding_hash: &str,
+        user_id_hash_salt: &str,
+    ) -> Result<(), sqlx::Error> {
+        record_identifier_fingerprint(
+            &tracing::Span::current(),
+            "discord_user_fingerprint",
+            discord_user_id,
+            user_id_hash_salt,
+        );
+
+        let now = Utc::now().timestamp();
+        let session = StoredSession {
+            discord_user_id: discord_user_id.to_string(),
+            created_at: now,
+            expires_at: now + ttl_seconds,
+            used_at: None,
+            locale: locale.map(|locale| locale.as_str().to_string()),
+            browser_binding_hash: Some(browser_binding_hash.to_string()),
+        };
+        let value =
+            serde_json::to_string(&session).map_err(|error| sqlx::Error::Encode(error.into()))?;
+
+        let mut connection = self.connection.clone();
+        let inserted: Option<String> = redis::cmd("SET")
+            .arg(session_key(state))
+            .arg(value)
+            .arg("NX")
+            .arg("EX")
+            .arg((ttl_seconds + TOMBSTONE_SECONDS).max(1))
+            .query_async(&mut connection)
+            .await
+            .map_err(redis_error)?;
+
+        match inserted {
+            Some(_) => Ok(()),
+            None => Err(sqlx::Error::Protocol(
+                "duplicate OAuth session state".to_string(),
+            )),
+        }
+    }
+
+    /// The consume script runs atomically on the server, so concurrent callbacks
+    /// cannot both consume a session.
+    #[tracing::instrument(name = "redis.consume_oauth_session", skip_all)]
+    async fn consume_oauth_session(
+        &self,
+        state: &str,
+        browser_binding_hash: Option<&str>,
+    ) -> Result<OAuthSession, SessionConsumeError> {
+        let now = Utc::now();
+        let mut connection = self.connection.clone();
+        let reply: Vec<String> = self
+            .consume_script
+            .key(session_key(state))
+            .arg(now.timestamp())
+            .arg(browser_binding_hash.unwrap_or_default())
+            .invoke_async(&mut connection)
+            .await
+            .map_err(|error| SessionConsumeError::Db(redis_error(error)))?;
+
+        let raw = match reply.as_slice() {
+            [status, raw] if status == "consumed" => raw,
+            [status] if status == "not_found" => return Err(SessionConsumeError::NotFound),
+            [status] if status == "already_used" => return Err(SessionConsumeError::AlreadyUsed),
+            [status] if status == "expired" => return Err(SessionConsumeError::Expired),
+            [status] if status == "browser_mismatch" => {
+                return Err(SessionConsumeError::BrowserMismatch);
+            }
+            other => {
+                return Err(SessionConsumeError::Db(sqlx::Error::Protocol(format!(
+                    "unexpected consume script reply {other:?}"
+                ))));
+            }
+        };
+
+        let session: StoredSession = serde_json::from_str(raw)
+            .map_err(|error| SessionConsumeError::Db(sqlx::Error::Decode(error.into())))?;
+        Ok(OAuthSession {
+            state: state.to_string(),
+            discord_user_id: session.discord_user_id,
+            expires_at: timestamp(session.expires_at).map_err(SessionConsumeError::Db)?,
+            used_at: Some(now),
+            created_at: timestamp(session.created_at).map_err(SessionConsumeError::Db)?,
+            locale: session.locale,
+            browser_binding_hash: session.browser_binding_hash,
+        })
+    }
+
+    async fn ping(&self) -> Option<Result<(), sqlx::Error>> {
+        let mut connection = self.connection.clone();
+        let result = redis::cmd("PING")
+            .query_async::<String>(&mut connection)
+            .await
+            .map(|_| ())
+            .map_err(redis_error);
+        Some(result)
+    }
+}
+
+/// These need a Redis server: `REDIS_URL=redis://127.0.0.1/ cargo test -- --ignored redis`.
+#[cfg(test)]
+mod tests {
+    use super::RedisSessionStore;
+    use crate::utils::{
+        functions::{SessionConsumeError, get_state_token},
+        store::SessionStore,
+    };
+    use std::sync::Arc;
+
+    const SALT: &str = "test-userid-hash-salt";
+
+    async fn connect() -> RedisSessionStore {
+        let url = std::env::var("REDIS_URL").expect("REDIS_URL should be set");
+        RedisSessionStore::connect(&url)
+            .await
+            .expect("redis should connect")
+    }
+
+    #[rocket::async_test]
+    #[ignore = "needs a Redis server at REDIS_URL"]
+    async fn redis_sessions_are_consumed_once() {
+        let store = connect().await;
+        let state = get_state_token();
+        store
+            .insert_oauth_session(&state, "discord_user", 300, None, "binding", SALT)
+            .await
+            .expect("session should insert");
+        store
+            .insert_oauth_session(&state, "discord_user", 300, None, "binding", SALT)
+            .await
+            .expect_err("a state should only be inserted once");
+
+        assert!(matches!(
+            store.consume_oauth_session(&state, Some("other")).await,
+            Err(SessionConsumeError::BrowserMismatch)
+        ));
+        let session = store
+            .consume_oauth_session(&state, Some("binding"))
+            .await
+            .expect("session should be consumed");
+        assert_eq!(session.discord_user_id, "discord_user");
+        assert!(session.used_at.is_some());
+
+        assert!(matches!(
+            store.consume_oauth_session(&state, Some("binding")).await,
+            Err(SessionConsumeError::AlreadyUsed)
+        ));
+        assert!(matches!(
+            store
+                .consume_oauth_session(&get_state_token(), Some("binding"))
+                .await,
+            Err(SessionConsumeError::NotFound)
+        ));
+    }
+
+    #[rocket::async_test]
+    #[ignore = "needs a Redis server at REDIS_URL"]
+    async fn redis_sessions_expire() {
+        let store = connect().await;
+        let state = get_state_token();
+        store
+            .insert_oauth_session(&state, "discord_user", -1, None, "binding", SALT)
+            .await
+            .expect("session should insert");
+
+        assert!(matches!(
+            store.consume_oauth_session(&state, Some("binding")).await,
+            Err(SessionConsumeError::Expired)
+        ));
+    }
+
+    #[rocket::async_test]
+    #[ignore = "needs a Redis server at REDIS_URL"]
+    async fn concurrent_redis_consumes_succeed_once() {
+        let store = Arc::new(connect().await);
+        let state = get_state_token();
+        store
+            .insert_oauth_session(&state, "discord_user", 300, None, "binding", SALT)
+            .await
+            .expect("session should insert");
+
+        let (first, second) = rocket::tokio::join!(
+            store.consume_oauth_session(&state, Some("binding")),
+            store.consume_oauth_session(&state, Some("binding")),
+        );
+        assert_eq!(
+            usize::from(first.is_ok()) + usize::from(second.is_ok()),
+            1,
+            "exactly one consume should win"
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use super::{
+    config::{AppConfig, optional_env},
+    lifecycle::Lifecycle,
+    observability::configure_oauth_scope,
+    structs::AdminApiToken,
+};
+
+use anyhow::Result;
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+
+/// Environment variables behind `ReloadableSettings`. They take precedence over the
+/// config file and secret files, and cannot change while the process runs.
+const RELOADABLE_ENV_VARS: [&str; 5] = [
+    "ANILIST_CLIENT_SECRET",
+    "OAUTH_CONTEXT_SIGNING_SECRET",
+    "OAUTH_CONTEXT_TTL_SECONDS",
+    "OAUTH_STATE_TTL_SECONDS",
+    "ADMIN_API_TOKENS",
+];
+
+/// Reloadable settings set as environment variables, which a reload can never change.
+fn env_pinned_settings() -> Vec<&'static str> {
+    RELOADABLE_ENV_VARS
+        .into_iter()
+        .filter(|name| optional_env(name).is_some())
+        .collect()
+}
+
+/// Settings that can be rotated without a restart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReloadableSettings {
+    pub client_secret: String,
+    pub context_signing_secret: String,
+    pub context_ttl_seconds: i64,
+    pub state_ttl_seconds: i64,
+    pub admin_tokens: Vec<AdminApiToken>,
+}
+
+impl From<&AppConfig> for ReloadableSettings {
+    fn from(config: &AppConfig) -> Self {
+        Self {
+            client_secret: config.client_secret.clone(),
+            context_signing_secret: config.context_signing_secret.clone(),
+            context_ttl_seconds: config.context_ttl_seconds,
+            state_ttl_seconds: config.state_ttl_seconds,
+            admin_tokens: config.admin_tokens.clone(),
+        }
+    }
+}
+
+impl ReloadableSettings {
+    /// Names of the settings that differ from `previous`. Never includes values.
+    fn changed_from(&self, previous: &Self) -> Vec<&'static str> {
+        [
+            (
+                "anilist_client_secret",
+                self.client_secret != previous.client_secret,
+            ),
+            (
+                "oauth_context_signing_secret",
+                self.context_signing_secret != previous.context_signing_secret,
+            ),
+            (
+                "oauth_context_ttl_seconds",
+                self.context_ttl_seconds != previous.context_ttl_seconds,
+            ),
+            (
+                "oauth_state_ttl_seconds",
+                self.state_ttl_seconds != previous.state_ttl_seconds,
+            ),
+            (
+                "admin_api_tokens",
+                self.admin_tokens != previous.admin_tokens,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(name, changed)| changed.then_some(name))
+        .collect()
+    }
+}
+
+/// Swappable handle to the current `ReloadableSettings`. Handlers take one snapshot
+/// with `current()`, so a reload never changes settings halfway through a request.
+/// Clones share state.
+#[derive(Debug, Clone)]
+pub struct SettingsHandle {
+    current: Arc<RwLock<Arc<ReloadableSettings>>>,
+}
+
+impl SettingsHandle {
+    pub fn new(settings: ReloadableSettings) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(Arc::new(settings))),
+        }
+    }
+
+    pub fn current(&self) -> Arc<ReloadableSettings> {
+        self.current
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Swaps in `next` and returns the names of the settings that changed.
+    pub fn replace(&self, next: ReloadableSettings) -> Vec<&'static str> {
+        let mut current = self
+            .current
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let changed = next.changed_from(&current);
+        *current = Arc::new(next);
+        changed
+    }
+}
+
+/// Loads and validates the configuration again, then swaps in its reloadable part.
+/// On error the current settings stay in place.
+pub fn reload_settings(
+    config_file: Option<&Path>,
+    settings: &SettingsHandle,
+) -> Result<Vec<&'static str>> {
+    let config = AppConfig::load(config_file)?;
+    Ok(settings.replace(ReloadableSettings::from(&config)))
+}
+
+/// Reloads settings whenever the process receives SIGHUP, until shutdown begins draining.
+#[cfg(unix)]
+pub fn spawn_reload_on_sighup(
+    config_file: Option<PathBuf>,
+    settings: SettingsHandle,
+    lifecycle: Lifecycle,
+) -> std::io::Result<()> {
+    use rocket::tokio::signal::unix::{SignalKind, signal};
+
+    let mut hangups = signal(SignalKind::hangup())?;
+    let task_lifecycle = lifecycle.clone();
+    lifecycle.spawn(async move {
+        loop {
+            rocket::tokio::select! {
+                biased;
+                _ = task_lifecycle.draining() => break,
+                received = hangups.recv() => {
+                    if received.is_none() {
+                        break;
+                    }
+                }
+            }
+
+            match reload_settings(config_file.as_deref(), &settings) {
+                Ok(changed) => {
+                    info!("Reloaded settings; changed: [{}]", changed.join(", "));
+                    let pinned = env_pinned_settings();
+                    if !pinned.is_empty() {
+                        warn!(
+                            "{} come from environment variables, which override the config \
+                             file and secret files; unset them to rotate on reload",
+                            pinned.join(", ")
+                        );
+                    }
+                }
+                Err(e) => {
+                    sentry::with_scope(
+                        |scope| configure_oauth_scope(scope, "settings.reload", None),
+                        || {
+                            sentry::capture_message(
+                                &format!("Settings reload failed: {e:#}"),
+                                sentry::Level::Error,
+                            )
+                        },
+                    );
+                    error!("Settings reload failed, keeping current settings: {e:#}");
+                }
+            }
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ReloadableSettings, SettingsHandle, env_pinned_settings};
+    use std::env;
+
+    fn settings(signing_secret: &str, state_ttl_seconds: i64) -> ReloadableSettings {
+        ReloadableSettings {
+            client_secret: "client-secret".to_string(),
+            context_signing_secret: signing_secret.to_string(),
+            context_ttl_seconds: 300,
+            state_ttl_seconds,
+            admin_tokens: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn replace_reports_changed_names_and_keeps_snapshots() {
+        let handle = SettingsHandle::new(settings("old-secret", 300));
+        let snapshot = handle.current();
+
+        let changed = handle.clone().replace(settings("new-secret", 600));
+        assert_eq!(
+            changed,
+            vec!["oauth_context_signing_secret", "oauth_state_ttl_seconds"]
+        );
+        assert_eq!(snapshot.context_signing_secret, "old-secret");
+        assert_eq!(handle.current().context_signing_secret, "new-secret");
+
+        assert!(handle.replace(settings("new-secret", 600)).is_empty());
+    }
+
+    #[test]
+    fn env_pinned_settings_lists_reloadable_env_vars() {
+        let key = "OAUTH_CONTEXT_TTL_SECONDS";
+        unsafe { env::set_var(key, "600") };
+        assert!(env_pinned_settings().contains(&key));
+
+        unsafe { env::set_var(key, " ") };
+        assert!(!env_pinned_settings().contains(&key));
+
+        unsafe { env::remove_var(key) };
+    }
+}
@@ -0,0 +1,280 @@
+use super::{
+    functions::{
+        anonymize_audit_events, delete_retired_oauth_sessions,
+        delete_stale_relink_required_credentials,
+    },
+    lifecycle::Lifecycle,
+    metrics::{RETENTION_LAST_SUCCESS_SECONDS, RETENTION_ROWS_TOTAL, RETENTION_RUNS_TOTAL},
+    observability::configure_oauth_scope,
+};
+
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::{Pool, Postgres};
+use std::time::Duration;
+
+pub const RETENTION_AUDIT_REASON: &str = "retention_policy";
+pub const RETENTION_JOB: &str = "retention";
+
+/// How many days each kind of row is kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// Used or expired OAuth sessions are deleted.
+    pub sessions_days: i64,
+    /// Credentials still waiting for a relink are deleted.
+    pub relink_required_days: i64,
+    /// Audit events are anonymized.
+    pub audit_events_days: i64,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            sessions_days: 30,
+            relink_required_days: 180,
+            audit_events_days: 365,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub struct RetentionReport {
+    pub sessions_deleted: u64,
+    pub credentials_deleted: u64,
+    pub audit_events_anonymized: u64,
+}
+
+/// Applies `policy` once. Deleted credentials are recorded as `unlinked` audit events
+/// so the trail shows why the link disappeared.
+pub async fn enforce_retention(
+    policy: &RetentionPolicy,
+    user_id_hash_salt: &str,
+    db: &Pool<Postgres>,
+) -> Result<RetentionReport, sqlx::Error> {
+    let sessions_deleted = delete_retired_oauth_sessions(policy.sessions_days, db).await?;
+
+    let credentials = delete_stale_relink_required_credentials(
+        policy.relink_required_days,
+        RETENTION_AUDIT_REASON,
+        db,
+    )
+    .await?;
+
+    let audit_events_anonymized =
+        anonymize_audit_events(policy.audit_events_days, user_id_hash_salt, db).await?;
+
+    Ok(RetentionReport {
+        sessions_deleted,
+        credentials_deleted: credentials.len() as u64,
+        audit_events_anonymized,
+    })
+}
+
+/// One scheduled run: enforces the policy and reports the outcome through tracing,
+/// metrics and (on failure) Sentry.
+#[tracing::instrument(name = "retention.run", skip_all)]
+pub async fn run_retention(policy: &RetentionPolicy, user_id_hash_salt: &str, db: &Pool<Postgres>) {
+    match enforce_retention(policy, user_id_hash_salt, db).await {
+        Ok(report) => {
+            metrics::counter!(RETENTION_RUNS_TOTAL, "outcome" => "success").increment(1);
+            for (table, action, rows) in [
+                ("oauth_sessions", "deleted", report.sessions_deleted),
+                ("oauth_credentials", "deleted", report.credentials_deleted),
+                (
+                    "oauth_audit_events",
+                    "anonymized",
+                    report.audit_events_anonymized,
+                ),
+            ] {
+                metrics::counter!(RETENTION_ROWS_TOTAL, "table" => table, "action" => action)
+                    .increment(rows);
+            }
+            metrics::gauge!(RETENTION_LAST_SUCCESS_SECONDS).set(Utc::now().timestamp() as f64);
+
+            info!(
+                "Retention policy enforced: {} sessions deleted, {} credentials deleted, \
+                 {} audit events anonymized",
+                report.sessions_deleted, report.credentials_deleted, report.audit_events_anonymized
+            );
+        }
+        Err(e) => {
+            metrics::counter!(RETENTION_RUNS_TOTAL, "outcome" => "failure").increment(1);
+            sentry::with_scope(
+                |scope| configure_oauth_scope(scope, "retention.run", None),
+                || sentry::capture_error(&e),
+            );
+            error!("Retention run failed: {e}");
+        }
+    }
+}
+
+/// Runs retention every `interval`, starting immediately, until shutdown begins draining.
+pub fn spawn_retention_job(
+    policy: RetentionPolicy,
+    interval: Duration,
+    user_id_hash_salt: String,
+    db: Pool<Postgres>,
+    lifecycle: Lifecycle,
+) {
+    lifecycle.heartbeats().register(RETENTION_JOB, interval);
+    let task_lifecycle = lifecycle.clone();
+    lifecycle.spawn(async move {
+        let mut ticker = rocket::tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(rocket::tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            rocket::tokio::select! {
+                biased;
+                _ = task_lifecycle.draining() => break,
+                _ = ticker.tick() => {}
+            }
+            run_retention(&policy, &user_id_hash_salt, &db).await;
+            task_lifecycle.heartbeats().beat(RETENTION_JOB);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RetentionPolicy, RetentionReport, enforce_retention, run_retention};
+    use crate::utils::{
+        functions::{fetch_audit_events, fetch_credential_by_discord_user},
+        metrics::RETENTION_ROWS_TOTAL,
+        observability::identifier_fingerprint,
+    };
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use sqlx::{Pool, Postgres};
+
+    const TEST_USERID_HASH_SALT: &str = "test-userid-hash-salt";
+
+    async fn seed(pool: &Pool<Postgres>) {
+        sqlx::query(
+            "INSERT INTO oauth_sessions (state, discord_user_id, expires_at, used_at, created_at) VALUES \
+             ('old-used', '111', NOW() - INTERVAL '40 days', NOW() - INTERVAL '40 days', NOW() - INTERVAL '40 days'), \
+             ('old-expired', '111', NOW() - INTERVAL '40 days', NULL, NOW() - INTERVAL '40 days'), \
+             ('recent-used', '111', NOW(), NOW(), NOW() - INTERVAL '1 day')",
+        )
+        .execute(pool)
+        .await
+        .expect("sessions should insert");
+
+        sqlx::query(
+            "INSERT INTO oauth_credentials \
+             (discord_user_id, anilist_id, access_token, relink_required_at, relink_reason) VALUES \
+             ('stale', 1, 'tok', NOW() - INTERVAL '200 days', 'token_revoked'), \
+             ('fresh', 2, 'tok', NOW() - INTERVAL '1 day', 'token_revoked'), \
+             ('healthy', 3, 'tok', NULL, NULL)",
+        )
+        .execute(pool)
+        .await
+        .expect("credentials should insert");
+
+        sqlx::query(
+            "INSERT INTO oauth_audit_events (discord_user_id, anilist_id, event, actor, reason, created_at) VALUES \
+             ('healthy', 3, 'relink_required', 'cli', 'old reason', NOW() - INTERVAL '400 days'), \
+             ('healthy', 3, 'linked', 'user', NULL, NOW() - INTERVAL '1 day')",
+        )
+        .execute(pool)
+        .await
+        .expect("audit events should insert");
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn enforce_retention_deletes_and_anonymizes_expired_rows(pool: Pool<Postgres>) {
+        seed(&pool).await;
+        let policy = RetentionPolicy::default();
+
+        let report = enforce_retention(&policy, TEST_USERID_HASH_SALT, &pool)
+            .await
+            .expect("retention should run");
+        assert_eq!(
+            report,
+            RetentionReport {
+                sessions_deleted: 2,
+                credentials_deleted: 1,
+                audit_events_anonymized: 1,
+            }
+        );
+
+        assert!(
+            fetch_credential_by_discord_user("stale", TEST_USERID_HASH_SALT, &pool)
+                .await
+                .expect("fetch should not error")
+                .is_none()
+        );
+        let unlinked = fetch_audit_events("stale", 10, TEST_USERID_HASH_SALT, &pool)
+            .await
+            .expect("audit events should load");
+        assert_eq!(unlinked[0].event, "unlinked");
+        assert_eq!(unlinked[0].reason.as_deref(), Some("retention_policy"));
+
+        let remaining = fetch_audit_events("healthy", 10, TEST_USERID_HASH_SALT, &pool)
+            .await
+            .expect("audit events should load");
+        assert_eq!(remaining.len(), 1);
+        let anonymized = fetch_audit_events(
+            &identifier_fingerprint("healthy", TEST_USERID_HASH_SALT),
+            10,
+            TEST_USERID_HASH_SALT,
+            &pool,
+        )
+        .await
+        .expect("audit events should load");
+        assert_eq!(anonymized.len(), 1);
+        assert_eq!(anonymized[0].anilist_id, None);
+        assert_eq!(anonymized[0].reason, None);
+
+        let second = enforce_retention(&policy, TEST_USERID_HASH_SALT, &pool)
+            .await
+            .expect("retention should run again");
+        assert_eq!(second, RetentionReport::default());
+
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn enforce_retention_keeps_credentials_when_the_audit_insert_fails(pool: Pool<Postgres>) {
+        seed(&pool).await;
+        sqlx::query(
+            "ALTER TABLE oauth_audit_events \
+             ADD CONSTRAINT reject_retention CHECK (reason IS DISTINCT FROM 'retention_policy')",
+        )
+        .execute(&pool)
+        .await
+        .expect("constraint should be added");
+
+        enforce_retention(&RetentionPolicy::default(), TEST_USERID_HASH_SALT, &pool)
+            .await
+            .expect_err("retention should fail when its audit rows are rejected");
+
+        assert!(
+            fetch_credential_by_discord_user("stale", TEST_USERID_HASH_SALT, &pool)
+                .await
+                .expect("fetch should not error")
+                .is_some(),
+            "the credential must survive a failed audit insert"
+        );
+
+        pool.close().await;
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn run_retention_reports_metrics(pool: Pool<Postgres>) {
+        seed(&pool).await;
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+
+        {
+            let _guard = metrics::set_default_local_recorder(&recorder);
+            run_retention(&RetentionPolicy::default(), TEST_USERID_HASH_SALT, &pool).await;
+        }
+
+        let rendered = handle.render();
+        assert!(rendered.contains(&format!(
+            "{RETENTION_ROWS_TOTAL}{{table=\"oauth_sessions\",action=\"deleted\"}} 2"
+        )));
+        assert!(rendered.contains("annie_mei_retention_runs_total{outcome=\"success\"} 1"));
+
+        pool.close().await;
+    }
+}
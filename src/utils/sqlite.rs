@@ -0,0 +1,375 @@
+use crate::utils::{
+    functions::{SessionConsumeError, UpsertOAuthCredentialsError, is_anilist_id_conflict},
+    i18n::Locale,
+    observability::record_identifier_fingerprint,
+    store::{CredentialStore, SessionStore},
+    structs::{OAuthCredential, OAuthSession},
+};
+
+use chrono::{DateTime, Utc};
+use sqlx::{
+    SqlitePool,
+    migrate::Migrator,
+    sqlite::{SqliteConnectOptions, SqliteJournalMode},
+};
+use std::{path::Path, time::Duration};
+
+/// Schema for the SQLite backend. Timestamps are stored as Unix seconds so they
+/// compare as numbers.
+pub static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("./migrations_sqlite");
+
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+const CREDENTIAL_COLUMNS: &str = "discord_user_id, anilist_id, access_token, refresh_token, \
+     token_expires_at, token_updated_at, relink_required_at, relink_reason, created_at";
+const SESSION_COLUMNS: &str =
+    "state, discord_user_id, expires_at, used_at, created_at, locale, browser_binding_hash";
+
+/// Opens (creating if needed) the database at `path` and applies its migrations.
+pub async fn connect_sqlite(path: &Path) -> Result<SqlitePool, sqlx::Error> {
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .busy_timeout(BUSY_TIMEOUT);
+    let pool = SqlitePool::connect_with(options).await?;
+    SQLITE_MIGRATOR.run(&pool).await?;
+    Ok(pool)
+}
+
+/// Sessions and credentials in a single SQLite file, for single-instance deployments.
+pub struct SqliteStore {
+    pool: SqlitePool,
+}
+
+impl SqliteStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+}
+
+#[rocket::async_trait]
+impl SessionStore for SqliteStore {
+    #[tracing::instrument(
+        name = "sqlite.insert_oauth_session",
+        skip_all,
+        fields(discord_user_fingerprint = tracing::field::Empty)
+    )]
+    async fn insert_oauth_session(
+        &self,
+        state: &str,
+        discord_user_id: &str,
+        ttl_seconds: i64,
+        locale: Option<Locale>,
+        browser_binding_hash: &str,
+        user_id_hash_salt: &str,
+    ) -> Result<(), sqlx::Error> {
+        record_identifier_fingerprint(
+            &tracing::Span::current(),
+            "discord_user_fingerprint",
+            discord_user_id,
+            user_id_hash_salt,
+        );
+
+        sqlx::query(
+            "INSERT INTO oauth_sessions (state, discord_user_id, expires_at, locale, browser_binding_hash) \
+             VALUES (?1, ?2, unixepoch() + ?3, ?4, ?5)",
+        )
+        .bind(state)
+        .bind(discord_user_id)
+        .bind(ttl_seconds)
+        .bind(locale.map(Locale::as_str))
+        .bind(browser_binding_hash)
+        .execute(&self.pool)
+        .await
+        .map(|_| ())
+    }
+
+    /// A single `UPDATE … RETURNING` claims the session, so concurrent callbacks
+    /// cannot both consume it.
+    #[tracing::instrument(name = "sqlite.consume_oauth_session", skip_all)]
+    async fn consume_oauth_session(
+        &self,
+        state: &str,
+        browser_binding_hash: Option<&str>,
+    ) -> Result<OAuthSession, SessionConsumeError> {
+        let session = sqlx::query_as::<_, OAuthSession>(&format!(
+            "UPDATE oauth_sessions \
+             SET used_at = unixepoch() \
+             WHERE state = ?1 AND used_at IS NULL AND expires_at > unixepoch() \
+               AND (browser_binding_hash IS NULL OR browser_binding_hash = ?2) \
+             RETURNING {SESSION_COLUMNS}"
+        ))
+        .bind(state)
+        .bind(browser_binding_hash)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(SessionConsumeError::Db)?;
+
+        if let Some(session) = session {
+            return Ok(session);
+        }
+
+        let diag = sqlx::query_as::<_, (Option<i64>, bool)>(
+            "SELECT used_at, expires_at <= unixepoch() FROM oauth_sessions WHERE state = ?1",
+        )
+        .bind(state)
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(SessionConsumeError::Db)?;
+
+        match diag {
+            None => Err(SessionConsumeError::NotFound),
+            Some((Some(_), _)) => Err(SessionConsumeError::AlreadyUsed),
+            Some((None, true)) => Err(SessionConsumeError::Expired),
+            Some((None, false)) => Err(SessionConsumeError::BrowserMismatch),
+        }
+    }
+}
+
+#[rocket::async_trait]
+impl CredentialStore for SqliteStore {
+    #[tracing::instrument(
+        name = "sqlite.upsert_oauth_credentials",
+        skip_all,
+        fields(discord_user_fingerprint = tracing::field::Empty)
+    )]
+    async fn upsert_oauth_credentials(
+        &self,
+        discord_user_id: &str,
+        anilist_id: i64,
+        access_token: &str,
+        refresh_token: Option<&str>,
+        token_expires_at: Option<DateTime<Utc>>,
+        user_id_hash_salt: &str,
+    ) -> Result<(), UpsertOAuthCredentialsError> {
+        record_identifier_fingerprint(
+            &tracing::Span::current(),
+            "discord_user_fingerprint",
+            discord_user_id,
+            user_id_hash_salt,
+        );
+
+        if let Some(existing) = self
+            .fetch_credential_by_anilist_id(anilist_id, user_id_hash_salt)
+            .await
+            .map_err(UpsertOAuthCredentialsError::Db)?
+            && existing.discord_user_id != discord_user_id
+        {
+            return Err(UpsertOAuthCredentialsError::AlreadyLinked);
+        }
+
+        sqlx::query(
+            "INSERT INTO oauth_credentials \
+             (discord_user_id, anilist_id, access_token, refresh_token, token_expires_at, token_updated_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5, unixepoch()) \
+             ON CONFLICT (discord_user_id) DO UPDATE SET \
+                 anilist_id = excluded.anilist_id, \
+                 access_token = excluded.access_token, \
+                 refresh_token = excluded.refresh_token, \
+                 token_expires_at = excluded.token_expires_at, \
+                 token_updated_at = unixepoch(), \
+                 relink_required_at = NULL, \
+                 relink_reason = NULL",
+        )
+        .bind(discord_user_id)
+        .bind(anilist_id)
+        .bind(access_token)
+        .bind(refresh_token)
+        .bind(token_expires_at.map(|expires_at| expires_at.timestamp()))
+        .execute(&self.pool)
+        .await
+        .map_err(|error| {
+            if is_anilist_id_conflict(&error) {
+                UpsertOAuthCredentialsError::AlreadyLinked
+            } else {
+                UpsertOAuthCredentialsError::Db(error)
+            }
+        })
+        .map(|_| ())
+    }
+
+    async fn fetch_credential_by_discord_user(
+        &self,
+        discord_user_id: &str,
+        _user_id_hash_salt: &str,
+    ) -> Result<Option<OAuthCredential>, sqlx::Error> {
+        sqlx::query_as::<_, OAuthCredential>(&format!(
+            "SELECT {CREDENTIAL_COLUMNS} FROM oauth_credentials WHERE discord_user_id = ?1"
+        ))
+        .bind(discord_user_id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    async fn fetch_credential_by_anilist_id(
+        &self,
+        anilist_id: i64,
+        _user_id_hash_salt: &str,
+    ) -> Result<Option<OAuthCredential>, sqlx::Error> {
+        sqlx::query_as::<_, OAuthCredential>(&format!(
+            "SELECT {CREDENTIAL_COLUMNS} FROM oauth_credentials WHERE anilist_id = ?1"
+        ))
+        .bind(anilist_id)
+        .fetch_optional(&self.pool)
+        .await
+    }
+
+    async fn mark_oauth_credentials_relink_required(
+        &self,
+        discord_user_id: &str,
+        reason: &str,
+        _user_id_hash_salt: &str,
+    ) -> Result<bool, sqlx::Error> {
+        sqlx::query(
+            "UPDATE oauth_credentials \
+             SET relink_required_at = unixepoch(), relink_reason = ?2 \
+             WHERE discord_user_id = ?1",
+        )
+        .bind(discord_user_id)
+        .bind(reason)
+        .execute(&self.pool)
+        .await
+        .map(|result| result.rows_affected() > 0)
+    }
+
+    async fn record_audit_event(
+        &self,
+        discord_user_id: &str,
+        anilist_id: Option<i64>,
+        event: &str,
+        actor: &str,
+        reason: Option<&str>,
+        _user_id_hash_salt: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO oauth_audit_events (discord_user_id, anilist_id, event, actor, reason) \
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )
+        .bind(discord_user_id)
+        .bind(anilist_id)
+        .bind(event)
+        .bind(actor)
+        .bind(reason)
+        .execute(&self.pool)
+        .await
+        .map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SqliteStore, connect_sqlite};
+    use crate::utils::{
+        functions::{SessionConsumeError, UpsertOAuthCredentialsError, is_anilist_id_conflict},
+        store::{CredentialStore, SessionStore},
+    };
+    use chrono::{Duration, Utc};
+
+    const SALT: &str = "test-userid-hash-salt";
+
+    async fn test_store() -> (SqliteStore, tempfile::TempDir) {
+        let dir = tempfile::tempdir().expect("temp dir should be created");
+        let pool = connect_sqlite(&dir.path().join("auth.db"))
+            .await
+            .expect("sqlite database should open");
+        (SqliteStore::new(pool), dir)
+    }
+
+    #[rocket::async_test]
+    async fn sessions_are_consumed_once() {
+        let (store, _dir) = test_store().await;
+        store
+            .insert_oauth_session("state", "discord_user", 300, None, "binding", SALT)
+            .await
+            .expect("session should insert");
+        store
+            .insert_oauth_session("expired", "discord_user", -1, None, "binding", SALT)
+            .await
+            .expect("session should insert");
+
+        assert!(matches!(
+            store.consume_oauth_session("state", Some("other")).await,
+            Err(SessionConsumeError::BrowserMismatch)
+        ));
+
+        let (first, second) = rocket::tokio::join!(
+            store.consume_oauth_session("state", Some("binding")),
+            store.consume_oauth_session("state", Some("binding")),
+        );
+        let session = first.or(second).expect("one consume should succeed");
+        assert_eq!(session.discord_user_id, "discord_user");
+        assert!(session.used_at.is_some());
+        assert!(matches!(
+            store.consume_oauth_session("state", Some("binding")).await,
+            Err(SessionConsumeError::AlreadyUsed)
+        ));
+
+        assert!(matches!(
+            store
+                .consume_oauth_session("expired", Some("binding"))
+                .await,
+            Err(SessionConsumeError::Expired)
+        ));
+        assert!(matches!(
+            store
+                .consume_oauth_session("missing", Some("binding"))
+                .await,
+            Err(SessionConsumeError::NotFound)
+        ));
+    }
+
+    #[rocket::async_test]
+    async fn credentials_keep_anilist_ids_unique() {
+        let (store, _dir) = test_store().await;
+        let expires_at = Utc::now() + Duration::hours(1);
+        store
+            .upsert_oauth_credentials(
+                "first",
+                42,
+                "token",
+                Some("refresh"),
+                Some(expires_at),
+                SALT,
+            )
+            .await
+            .expect("credential should insert");
+        assert!(
+            store
+                .mark_oauth_credentials_relink_required("first", "token_revoked", SALT)
+                .await
+                .expect("credential should be marked")
+        );
+
+        assert!(matches!(
+            store
+                .upsert_oauth_credentials("second", 42, "other", None, None, SALT)
+                .await,
+            Err(UpsertOAuthCredentialsError::AlreadyLinked)
+        ));
+        let error = sqlx::query(
+            "INSERT INTO oauth_credentials (discord_user_id, anilist_id, access_token) \
+             VALUES ('second', 42, 'other')",
+        )
+        .execute(&store.pool)
+        .await
+        .expect_err("the unique constraint should reject the insert");
+        assert!(is_anilist_id_conflict(&error));
+
+        store
+            .upsert_oauth_credentials("first", 42, "relinked", None, Some(expires_at), SALT)
+            .await
+            .expect("relink should update the credential");
+        let credential = store
+            .fetch_credential_by_anilist_id(42, SALT)
+            .await
+            .expect("lookup should succeed")
+            .expect("credential should exist");
+        assert_eq!(credential.discord_user_id, "first");
+        assert_eq!(credential.access_token, "relinked");
+        assert_eq!(
+            credential.token_expires_at.map(|at| at.timestamp()),
+            Some(expires_at.timestamp())
+        );
+        assert!(credential.relink_required_at.is_none());
+    }
+}
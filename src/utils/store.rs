@@ -0,0 +1,556 @@
+use crate::utils::{
+    functions::{
+        SessionConsumeError, UpsertOAuthCredentialsError, consume_oauth_session,
+        fetch_credential_by_anilist_id, fetch_credential_by_discord_user, insert_oauth_session,
+        mark_oauth_credentials_relink_required, record_audit_event, upsert_oauth_credentials,
+    },
+    i18n::Locale,
+    sqlite::SqliteStore,
+    structs::{AuditEvent, OAuthCredential, OAuthSession},
+};
+
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{PgPool, SqlitePool};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+/// Which store backs OAuth sessions and linked credentials.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageBackend {
+    Postgres,
+    /// A local SQLite file, for single-instance self-hosting.
+    Sqlite,
+    /// Lost on restart; for tests and local demos without Postgres.
+    Memory,
+}
+
+impl StorageBackend {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Postgres => "postgres",
+            Self::Sqlite => "sqlite",
+            Self::Memory => "memory",
+        }
+    }
+}
+
+/// Short-lived state created by `start` and consumed once by the callback.
+#[rocket::async_trait]
+pub trait SessionStore: Send + Sync {
+    async fn insert_oauth_session(
+        &self,
+        state: &str,
+        discord_user_id: &str,
+        ttl_seconds: i64,
+        locale: Option<Locale>,
+        browser_binding_hash: &str,
+        user_id_hash_salt: &str,
+    ) -> Result<(), sqlx::Error>;
+
+    /// Marks the session used, at most once. See [`consume_oauth_session`].
+    async fn consume_oauth_session(
+        &self,
+        state: &str,
+        browser_binding_hash: Option<&str>,
+    ) -> Result<OAuthSession, SessionConsumeError>;
+
+    /// Readiness ping for a session store kept outside the storage backend, i.e.
+    /// Redis. `None` when the backend's own checks already cover the sessions.
+    async fn ping(&self) -> Option<Result<(), sqlx::Error>> {
+        None
+    }
+}
+
+/// Linked AniList credentials and their audit trail.
+#[rocket::async_trait]
+pub trait CredentialStore: Send + Sync {
+    async fn upsert_oauth_credentials(
+        &self,
+        discord_user_id: &str,
+        anilist_id: i64,
+        access_token: &str,
+        refresh_token: Option<&str>,
+        token_expires_at: Option<DateTime<Utc>>,
+        user_id_hash_salt: &str,
+    ) -> Result<(), UpsertOAuthCredentialsError>;
+
+    async fn fetch_credential_by_discord_user(
+        &self,
+        discord_user_id: &str,
+        user_id_hash_salt: &str,
+    ) -> Result<Option<OAuthCredential>, sqlx::Error>;
+
+    async fn fetch_credential_by_anilist_id(
+        &self,
+        anilist_id: i64,
+        user_id_hash_salt: &str,
+    ) -> Result<Option<OAuthCredential>, sqlx::Error>;
+
+    /// Returns whether a credential was marked.
+    async fn mark_oauth_credentials_relink_required(
+        &self,
+        discord_user_id: &str,
+        reason: &str,
+        user_id_hash_salt: &str,
+    ) -> Result<bool, sqlx::Error>;
+
+    async fn record_audit_event(
+        &self,
+        discord_user_id: &str,
+        anilist_id: Option<i64>,
+        event: &str,
+        actor: &str,
+        reason: Option<&str>,
+        user_id_hash_salt: &str,
+    ) -> Result<(), sqlx::Error>;
+}
+
+/// The stores used by the OAuth flow.
+#[derive(Clone)]
+pub struct Storage {
+    pub sessions: Arc<dyn SessionStore>,
+    pub credentials: Arc<dyn CredentialStore>,
+}
+
+impl Storage {
+    pub fn postgres(pool: PgPool) -> Self {
+        let store = Arc::new(PostgresStore { pool });
+        Self {
+            sessions: store.clone(),
+            credentials: store,
+        }
+    }
+
+    pub fn sqlite(pool: SqlitePool) -> Self {
+        let store = Arc::new(SqliteStore::new(pool));
+        Self {
+            sessions: store.clone(),
+            credentials: store,
+        }
+    }
+
+    pub fn memory() -> Self {
+        let store = Arc::new(MemoryStore::default());
+        Self {
+            sessions: store.clone(),
+            credentials: store,
+        }
+    }
+}
+
+pub struct PostgresStore {
+    pool: PgPool,
+}
+
+#[rocket::async_trait]
+impl SessionStore for PostgresStore {
+    async fn insert_oauth_session(
+        &self,
+        state: &str,
+        discord_user_id: &str,
+        ttl_seconds: i64,
+        locale: Option<Locale>,
+        browser_binding_hash: &str,
+        user_id_hash_salt: &str,
+    ) -> Result<(), sqlx::Error> {
+        insert_oauth_session(
+            state,
+            discord_user_id,
+            ttl_seconds,
+            locale,
+            browser_binding_hash,
+            user_id_hash_salt,
+            &self.pool,
+        )
+        .await
+    }
+
+    async fn consume_oauth_session(
+        &self,
+        state: &str,
+        browser_binding_hash: Option<&str>,
+    ) -> Result<OAuthSession, SessionConsumeError> {
+        consume_oauth_session(state, browser_binding_hash, &self.pool).await
+    }
+}
+
+#[rocket::async_trait]
+impl CredentialStore for PostgresStore {
+    async fn upsert_oauth_credentials(
+        &self,
+        discord_user_id: &str,
+        anilist_id: i64,
+        access_token: &str,
+        refresh_token: Option<&str>,
+        token_expires_at: Option<DateTime<Utc>>,
+        user_id_hash_salt: &str,
+    ) -> Result<(), UpsertOAuthCredentialsError> {
+        upsert_oauth_credentials(
+            discord_user_id,
+            anilist_id,
+            access_token,
+            refresh_token,
+            token_expires_at,
+            user_id_hash_salt,
+            &self.pool,
+        )
+        .await
+    }
+
+    async fn fetch_credential_by_discord_user(
+        &self,
+        discord_user_id: &str,
+        user_id_hash_salt: &str,
+    ) -> Result<Option<OAuthCredential>, sqlx::Error> {
+        fetch_credential_by_discord_user(discord_user_id, user_id_hash_salt, &self.pool).await
+    }
+
+    async fn fetch_credential_by_anilist_id(
+        &self,
+        anilist_id: i64,
+        user_id_hash_salt: &str,
+    ) -> Result<Option<OAuthCredential>, sqlx::Error> {
+        fetch_credential_by_anilist_id(anilist_id, user_id_hash_salt, &self.pool).await
+    }
+
+    async fn mark_oauth_credentials_relink_required(
+        &self,
+        discord_user_id: &str,
+        reason: &str,
+        user_id_hash_salt: &str,
+    ) -> Result<bool, sqlx::Error> {
+        mark_oauth_credentials_relink_required(
+            discord_user_id,
+            reason,
+            user_id_hash_salt,
+            &self.pool,
+        )
+        .await
+    }
+
+    async fn record_audit_event(
+        &self,
+        discord_user_id: &str,
+        anilist_id: Option<i64>,
+        event: &str,
+        actor: &str,
+        reason: Option<&str>,
+        user_id_hash_salt: &str,
+    ) -> Result<(), sqlx::Error> {
+        record_audit_event(
+            discord_user_id,
+            anilist_id,
+            event,
+            actor,
+            reason,
+            user_id_hash_salt,
+            &self.pool,
+        )
+        .await
+    }
+}
+
+/// How long a session is kept after it expires, so replays and late callbacks get
+/// `AlreadyUsed` or `Expired` rather than `NotFound`.
+const MEMORY_SESSION_GRACE_SECONDS: i64 = 3600;
+
+/// Keeps everything in process memory with the same semantics as Postgres: sessions
+/// are consumed at most once and an AniList account links to one Discord user.
+#[derive(Default)]
+pub struct MemoryStore {
+    inner: Mutex<MemoryTables>,
+}
+
+#[derive(Default)]
+struct MemoryTables {
+    sessions: HashMap<String, OAuthSession>,
+    credentials: HashMap<String, OAuthCredential>,
+    audit_events: Vec<AuditEvent>,
+}
+
+impl MemoryStore {
+    fn tables(&self) -> std::sync::MutexGuard<'_, MemoryTables> {
+        self.inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[rocket::async_trait]
+impl SessionStore for MemoryStore {
+    async fn insert_oauth_session(
+        &self,
+        state: &str,
+        discord_user_id: &str,
+        ttl_seconds: i64,
+        locale: Option<Locale>,
+        browser_binding_hash: &str,
+        _user_id_hash_salt: &str,
+    ) -> Result<(), sqlx::Error> {
+        let now = Utc::now();
+        let mut tables = self.tables();
+        let prune_before = now - Duration::seconds(MEMORY_SESSION_GRACE_SECONDS);
+        tables
+            .sessions
+            .retain(|_, session| session.expires_at > prune_before);
+        if tables.sessions.contains_key(state) {
+            return Err(sqlx::Error::Protocol(
+                "duplicate OAuth session state".to_string(),
+            ));
+        }
+
+        tables.sessions.insert(
+            state.to_string(),
+            OAuthSession {
+                state: state.to_string(),
+                discord_user_id: discord_user_id.to_string(),
+                expires_at: now + Duration::seconds(ttl_seconds),
+                used_at: None,
+                created_at: now,
+                locale: locale.map(|locale| locale.as_str().to_string()),
+                browser_binding_hash: Some(browser_binding_hash.to_string()),
+            },
+        );
+        Ok(())
+    }
+
+    async fn consume_oauth_session(
+        &self,
+        state: &str,
+        browser_binding_hash: Option<&str>,
+    ) -> Result<OAuthSession, SessionConsumeError> {
+        let mut tables = self.tables();
+        let Some(session) = tables.sessions.get_mut(state) else {
+            return Err(SessionConsumeError::NotFound);
+        };
+
+        let now = Utc::now();
+        if session.used_at.is_some() {
+            return Err(SessionConsumeError::AlreadyUsed);
+        }
+        if session.expires_at <= now {
+            return Err(SessionConsumeError::Expired);
+        }
+        if session
+            .browser_binding_hash
+            .as_deref()
+            .is_some_and(|expected| Some(expected) != browser_binding_hash)
+        {
+            return Err(SessionConsumeError::BrowserMismatch);
+        }
+
+        session.used_at = Some(now);
+        Ok(session.clone())
+    }
+}
+
+#[rocket::async_trait]
+impl CredentialStore for MemoryStore {
+    async fn upsert_oauth_credentials(
+        &self,
+        discord_user_id: &str,
+        anilist_id: i64,
+        access_token: &str,
+        refresh_token: Option<&str>,
+        token_expires_at: Option<DateTime<Utc>>,
+        _user_id_hash_salt: &str,
+    ) -> Result<(), UpsertOAuthCredentialsError> {
+        let mut tables = self.tables();
+        if tables.credentials.values().any(|credential| {
+            credential.anilist_id == anilist_id && credential.discord_user_id != discord_user_id
+        }) {
+            return Err(UpsertOAuthCredentialsError::AlreadyLinked);
+        }
+
+        let now = Utc::now();
+        let created_at = tables
+            .credentials
+            .get(discord_user_id)
+            .map_or(now, |existing| existing.created_at);
+        tables.credentials.insert(
+            discord_user_id.to_string(),
+            OAuthCredential {
+                discord_user_id: discord_user_id.to_string(),
+                anilist_id,
+                access_token: access_token.to_string(),
+                refresh_token: refresh_token.map(str::to_string),
+                token_expires_at,
+                token_updated_at: now,
+                relink_required_at: None,
+                relink_reason: None,
+                created_at,
+            },
+        );
+        Ok(())
+    }
+
+    async fn fetch_credential_by_discord_user(
+        &self,
+        discord_user_id: &str,
+        _user_id_hash_salt: &str,
+    ) -> Result<Option<OAuthCredential>, sqlx::Error> {
+        Ok(self.tables().credentials.get(discord_user_id).cloned())
+    }
+
+    async fn fetch_credential_by_anilist_id(
+        &self,
+        anilist_id: i64,
+        _user_id_hash_salt: &str,
+    ) -> Result<Option<OAuthCredential>, sqlx::Error> {
+        Ok(self
+            .tables()
+            .credentials
+            .values()
+            .find(|credential| credential.anilist_id == anilist_id)
+            .cloned())
+    }
+
+    async fn mark_oauth_credentials_relink_required(
+        &self,
+        discord_user_id: &str,
+        reason: &str,
+        _user_id_hash_salt: &str,
+    ) -> Result<bool, sqlx::Error> {
+        let mut tables = self.tables();
+        let Some(credential) = tables.credentials.get_mut(discord_user_id) else {
+            return Ok(false);
+        };
+
+        credential.relink_required_at = Some(Utc::now());
+        credential.relink_reason = Some(reason.to_string());
+        Ok(true)
+    }
+
+    async fn record_audit_event(
+        &self,
+        discord_user_id: &str,
+        anilist_id: Option<i64>,
+        event: &str,
+        actor: &str,
+        reason: Option<&str>,
+        _user_id_hash_salt: &str,
+    ) -> Result<(), sqlx::Error> {
+        let mut tables = self.tables();
+        let id = i64::try_from(tables.audit_events.len()).unwrap_or(i64::MAX) + 1;
+        tables.audit_events.push(AuditEvent {
+            id,
+            discord_user_id: discord_user_id.to_string(),
+            anilist_id,
+            event: event.to_string(),
+            actor: actor.to_string(),
+            reason: reason.map(str::to_string),
+            created_at: Utc::now(),
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CredentialStore, MemoryStore, SessionStore};
+    use crate::utils::functions::{SessionConsumeError, UpsertOAuthCredentialsError};
+
+    const SALT: &str = "test-userid-hash-salt";
+
+    #[rocket::async_test]
+    async fn memory_sessions_are_consumed_once() {
+        let store = MemoryStore::default();
+        store
+            .insert_oauth_session("state", "discord_user", 300, None, "binding", SALT)
+            .await
+            .expect("session should insert");
+
+        assert!(matches!(
+            store.consume_oauth_session("state", Some("other")).await,
+            Err(SessionConsumeError::BrowserMismatch)
+        ));
+        let session = store
+            .consume_oauth_session("state", Some("binding"))
+            .await
+            .expect("session should be consumed");
+        assert_eq!(session.discord_user_id, "discord_user");
+        assert!(session.used_at.is_some());
+
+        assert!(matches!(
+            store.consume_oauth_session("state", Some("binding")).await,
+            Err(SessionConsumeError::AlreadyUsed)
+        ));
+        assert!(matches!(
+            store
+                .consume_oauth_session("missing", Some("binding"))
+                .await,
+            Err(SessionConsumeError::NotFound)
+        ));
+    }
+
+    #[rocket::async_test]
+    async fn memory_sessions_expire() {
+        let store = MemoryStore::default();
+        store
+            .insert_oauth_session("state", "discord_user", -1, None, "binding", SALT)
+            .await
+            .expect("session should insert");
+
+        assert!(matches!(
+            store.consume_oauth_session("state", Some("binding")).await,
+            Err(SessionConsumeError::Expired)
+        ));
+    }
+
+    #[rocket::async_test]
+    async fn memory_sessions_are_pruned_an_hour_after_expiry() {
+        let store = MemoryStore::default();
+        store
+            .insert_oauth_session("stale", "discord_user", -3601, None, "binding", SALT)
+            .await
+            .expect("session should insert");
+        store
+            .insert_oauth_session("recent", "discord_user", -60, None, "binding", SALT)
+            .await
+            .expect("session should insert");
+
+        assert!(matches!(
+            store.consume_oauth_session("stale", Some("binding")).await,
+            Err(SessionConsumeError::NotFound)
+        ));
+        assert!(matches!(
+            store.consume_oauth_session("recent", Some("binding")).await,
+            Err(SessionConsumeError::Expired)
+        ));
+    }
+
+    #[rocket::async_test]
+    async fn memory_credentials_keep_anilist_ids_unique() {
+        let store = MemoryStore::default();
+        store
+            .upsert_oauth_credentials("first", 42, "token", None, None, SALT)
+            .await
+            .expect("credential should insert");
+        store
+            .mark_oauth_credentials_relink_required("first", "token_revoked", SALT)
+            .await
+            .expect("credential should be marked");
+
+        assert!(matches!(
+            store
+                .upsert_oauth_credentials("second", 42, "other", None, None, SALT)
+                .await,
+            Err(UpsertOAuthCredentialsError::AlreadyLinked)
+        ));
+
+        store
+            .upsert_oauth_credentials("first", 42, "relinked", None, None, SALT)
+            .await
+            .expect("relink should update the credential");
+        let credential = store
+            .fetch_credential_by_anilist_id(42, SALT)
+            .await
+            .expect("lookup should succeed")
+            .expect("credential should exist");
+        assert_eq!(credential.discord_user_id, "first");
+        assert_eq!(credential.access_token, "relinked");
+        assert!(credential.relink_required_at.is_none());
+    }
+}
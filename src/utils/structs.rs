@@ -1,19 +1,30 @@
 use chrono::{DateTime, Utc};
-use serde::Deserialize;
-use sqlx::PgPool;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    anilist_client::AniListClient, database::Database, i18n::Locale, lifecycle::Lifecycle,
+    pages::Pages, rate_limit::RateLimits, reload::SettingsHandle, store::Storage,
+};
 
 pub struct MyState {
     pub client_id: String,
-    pub client_secret: String,
     pub redirect_uri: String,
-    pub context_signing_secret: String,
     pub user_id_hash_salt: String,
-    pub context_ttl_seconds: i64,
-    pub state_ttl_seconds: i64,
     pub token_endpoint: String,
     pub user_endpoint: String,
-    pub client: reqwest::Client,
-    pub pool: PgPool,
+    pub client: AniListClient,
+    /// Sessions and credentials for the OAuth flow.
+    pub storage: Storage,
+    /// Backs the admin API, exports and background jobs. `None` unless
+    /// `STORAGE_BACKEND=postgres`.
+    pub database: Option<Database>,
+    /// Secrets and TTLs that SIGHUP can reload.
+    pub settings: SettingsHandle,
+    pub rate_limits: RateLimits,
+    pub pages: Pages,
+    /// Show the consent page before redirecting to AniList.
+    pub consent_page_enabled: bool,
+    pub lifecycle: Lifecycle,
 }
 
 #[derive(Debug, Deserialize, PartialEq, Eq)]
@@ -25,6 +36,15 @@ pub struct OAuthContextPayload {
     pub nonce: String,
     pub iat: i64,
     pub exp: i64,
+    /// Discord interaction locale, e.g. `ja` or `pt-BR`.
+    pub locale: Option<String>,
+    /// Shown on the consent page so users can spot a forwarded link.
+    pub discord_username: Option<String>,
+    /// Discord avatar hash, resolved against the Discord CDN.
+    pub discord_avatar: Option<String>,
+    /// What the context may be used for. `export` contexts are only accepted by the
+    /// data export; anything else only by `start`.
+    pub purpose: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -42,7 +62,7 @@ pub struct TokenErrorResponse {
     pub error_description: Option<String>,
 }
 
-#[derive(Debug, sqlx::FromRow)]
+#[derive(Debug, Clone, sqlx::FromRow)]
 pub struct OAuthCredential {
     pub discord_user_id: String,
     pub anilist_id: i64,
@@ -55,13 +75,156 @@ pub struct OAuthCredential {
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, sqlx::FromRow)]
+/// Operator-facing view of a credential with tokens masked.
+#[derive(Debug, Serialize)]
+pub struct CredentialSummary {
+    pub discord_user_id: String,
+    pub anilist_id: i64,
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub token_expires_at: Option<DateTime<Utc>>,
+    pub token_updated_at: DateTime<Utc>,
+    pub relink_required_at: Option<DateTime<Utc>>,
+    pub relink_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<&OAuthCredential> for CredentialSummary {
+    fn from(credential: &OAuthCredential) -> Self {
+        Self {
+            discord_user_id: credential.discord_user_id.clone(),
+            anilist_id: credential.anilist_id,
+            access_token: mask_token(&credential.access_token),
+            refresh_token: credential.refresh_token.as_deref().map(mask_token),
+            token_expires_at: credential.token_expires_at,
+            token_updated_at: credential.token_updated_at,
+            relink_required_at: credential.relink_required_at,
+            relink_reason: credential.relink_reason.clone(),
+            created_at: credential.created_at,
+        }
+    }
+}
+
+/// Keeps only the last four characters so operators can tell tokens apart.
+fn mask_token(token: &str) -> String {
+    let char_count = token.chars().count();
+    if char_count <= 8 {
+        return "********".to_string();
+    }
+
+    let visible: String = token.chars().skip(char_count - 4).collect();
+    format!("********{visible}")
+}
+
+#[derive(Debug, Clone, sqlx::FromRow)]
 pub struct OAuthSession {
     pub state: String,
     pub discord_user_id: String,
     pub expires_at: DateTime<Utc>,
     pub used_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    pub locale: Option<String>,
+    pub browser_binding_hash: Option<String>,
+}
+
+/// Session history for data exports. The state and browser binding are omitted.
+#[derive(Debug, Serialize)]
+pub struct SessionSummary {
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+    pub locale: Option<String>,
+}
+
+impl From<&OAuthSession> for SessionSummary {
+    fn from(session: &OAuthSession) -> Self {
+        Self {
+            created_at: session.created_at,
+            expires_at: session.expires_at,
+            used_at: session.used_at,
+            locale: session.locale.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RelinkReason {
+    pub reason: Option<String>,
+    pub actor: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Everything stored about one Discord user, for subject access requests.
+#[derive(Debug, Serialize)]
+pub struct UserDataExport {
+    pub discord_user_id: String,
+    pub generated_at: DateTime<Utc>,
+    pub credential: Option<CredentialSummary>,
+    pub sessions: Vec<SessionSummary>,
+    pub audit_events: Vec<AuditEvent>,
+    pub relink_reasons: Vec<RelinkReason>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AuditEvent {
+    pub id: i64,
+    pub discord_user_id: String,
+    pub anilist_id: Option<i64>,
+    pub event: String,
+    pub actor: String,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default)]
+pub struct CredentialSearch {
+    pub discord_user_id: Option<String>,
+    pub anilist_id: Option<i64>,
+    pub relink_required: Option<bool>,
+    pub limit: i64,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    Failed,
+    ChecksumMismatch,
+    Unknown,
+}
+
+impl MigrationState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Applied => "applied",
+            Self::Pending => "pending",
+            Self::Failed => "failed",
+            Self::ChecksumMismatch => "checksum_mismatch",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+/// Outcome of probing a stored credential against AniList.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialHealth {
+    Valid,
+    /// AniList rejected the token and the credential was just marked for relink.
+    TokenRevoked,
+    /// AniList answered for a different account; the credential was just marked for relink.
+    AnilistIdMismatch,
+    /// The credential already needed a relink (including expiry detected now).
+    RelinkRequired,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+    pub installed_on: Option<DateTime<Utc>>,
 }
 
 #[derive(Deserialize)]
@@ -80,8 +243,59 @@ pub struct Viewer {
     pub id: i64,
 }
 
-/// Carries the Discord user ID recovered from the validated OAuth session.
-pub struct StateToken(pub String);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdminScope {
+    Read,
+    Write,
+}
+
+/// Admin bearer token loaded from config. Only a BLAKE3 hash of the secret is kept.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdminApiToken {
+    pub name: String,
+    pub scopes: Vec<AdminScope>,
+    token_hash: blake3::Hash,
+}
+
+impl AdminApiToken {
+    pub fn new(name: String, scopes: Vec<AdminScope>, token: &str) -> Self {
+        Self {
+            name,
+            scopes,
+            token_hash: blake3::hash(token.as_bytes()),
+        }
+    }
+
+    /// Compares in constant time via `blake3::Hash`'s `PartialEq`.
+    pub fn matches(&self, presented: &str) -> bool {
+        self.token_hash == blake3::hash(presented.as_bytes())
+    }
+}
+
+/// Name of an admin token that carries the `read` scope.
+pub struct AdminRead(pub String);
+
+/// Name of an admin token that carries the `write` scope.
+pub struct AdminWrite(pub String);
+
+#[derive(Debug, Serialize)]
+pub struct AdminErrorBody {
+    pub error: &'static str,
+}
+
+#[derive(Debug)]
+pub enum AdminAuthError {
+    Missing,
+    Invalid,
+    InsufficientScope,
+    Internal,
+}
+
+/// Carries the Discord user ID and locale recovered from the validated OAuth session.
+pub struct StateToken {
+    pub discord_user_id: String,
+    pub locale: Option<Locale>,
+}
 
 #[derive(Debug)]
 pub enum StateTokenError {
@@ -89,12 +303,48 @@ pub enum StateTokenError {
     Invalid,
     Expired,
     Replayed,
+    BrowserMismatch,
     Internal,
 }
 
+/// Body posted by the consent page's Continue button.
+#[derive(FromForm)]
+pub struct StartForm<'r> {
+    pub ctx: &'r str,
+}
+
+/// Request guard that spends one per-IP rate limit token.
+pub struct ClientRateLimit;
+
+/// Per-request nonce allowing the page's inline `<style>` under the CSP.
+#[derive(Debug, Clone)]
+pub struct CspNonce(pub String);
+
+/// Correlation ID shown on error pages so users can quote it to support.
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// What an HTML page needs from the request: templates, the CSP nonce, the request ID
+/// and the negotiated locale.
+pub struct PageContext {
+    pub pages: Pages,
+    pub nonce: CspNonce,
+    pub request_id: RequestId,
+    pub locale: Locale,
+}
+
+/// JSON body for error responses when the client prefers `application/json`.
+#[derive(Debug, Serialize)]
+pub struct ErrorBody {
+    pub error: &'static str,
+    pub message: &'static str,
+    pub request_id: String,
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{OAuthContextPayload, TokenResponse};
+    use super::{CredentialSummary, OAuthContextPayload, OAuthCredential, TokenResponse};
+    use chrono::Utc;
 
     #[test]
     fn token_response_deserializes_full_payload() {
@@ -137,4 +387,27 @@ mod tests {
         assert_eq!(payload.discord_user_id, "123456789012345678");
         assert_eq!(payload.guild_id.as_deref(), Some("987654321098765432"));
     }
+
+    #[test]
+    fn credential_summary_masks_tokens() {
+        let now = Utc::now();
+        let credential = OAuthCredential {
+            discord_user_id: "123".to_string(),
+            anilist_id: 42,
+            access_token: "eyJhbGciOiJSUzI1NiJ9.secret-part.sig1234".to_string(),
+            refresh_token: Some("short".to_string()),
+            token_expires_at: None,
+            token_updated_at: now,
+            relink_required_at: None,
+            relink_reason: None,
+            created_at: now,
+        };
+
+        let summary = CredentialSummary::from(&credential);
+        assert_eq!(summary.access_token, "********1234");
+        assert_eq!(summary.refresh_token.as_deref(), Some("********"));
+
+        let json = serde_json::to_string(&summary).unwrap();
+        assert!(!json.contains("secret-part"));
+    }
 }